@@ -0,0 +1,184 @@
+//! Perfect play on a classic 3x3 board for the program's side of a
+//! single-player game. The search runs on-chain after every human move, so
+//! boards are bitmasks of tiles (bit `row * 3 + col`) to keep it cheap.
+
+use anchor_lang::prelude::*;
+
+use crate::state::game::Sign;
+
+pub const AI_SEED: &[u8] = b"ai";
+
+/// The program's own seat in a single-player game
+pub fn program_player() -> Pubkey {
+    Pubkey::find_program_address(&[AI_SEED], &crate::ID).0
+}
+
+const FULL_BOARD: u16 = 0b111_111_111;
+
+const LINES: [u16; 8] = [
+    0b000_000_111,
+    0b000_111_000,
+    0b111_000_000,
+    0b001_001_001,
+    0b010_010_010,
+    0b100_100_100,
+    0b100_010_001,
+    0b001_010_100,
+];
+
+/// Center and corners first, which lets alpha-beta pruning cut the search
+/// short sooner
+const MOVE_ORDER: [usize; 9] = [4, 0, 2, 6, 8, 1, 3, 5, 7];
+
+fn has_line(tiles: u16) -> bool {
+    LINES.iter().any(|&line| line & !tiles == 0)
+}
+
+fn empty_tiles(taken: u16) -> impl Iterator<Item = usize> {
+    MOVE_ORDER
+        .into_iter()
+        .filter(move |tile| taken & (1 << tile) == 0)
+}
+
+/// Score of playing `tile` for the player holding `mine`: 1 for a win, -1
+/// for a loss and 0 for a tie. Under misère rules completing a line loses.
+fn score_move(
+    mine: u16,
+    theirs: u16,
+    tile: usize,
+    misere: bool,
+    window: (i8, i8),
+    nodes: &mut u32,
+) -> i8 {
+    *nodes += 1;
+    let mine = mine | (1 << tile);
+    if has_line(mine) {
+        return if misere { -1 } else { 1 };
+    }
+    if mine | theirs == FULL_BOARD {
+        return 0;
+    }
+    let (alpha, beta) = window;
+    -negamax(theirs, mine, misere, (-beta, -alpha), nodes)
+}
+
+/// Score of the best move for the player about to move, who holds `mine`,
+/// pruning lines that can't end up inside the `(alpha, beta)` window.
+fn negamax(mine: u16, theirs: u16, misere: bool, window: (i8, i8), nodes: &mut u32) -> i8 {
+    // Taking a win right away saves searching anything else
+    if !misere && empty_tiles(mine | theirs).any(|tile| has_line(mine | (1 << tile))) {
+        *nodes += 1;
+        return 1;
+    }
+    let (mut alpha, beta) = window;
+    let mut best = -1;
+    for tile in empty_tiles(mine | theirs) {
+        let score = score_move(mine, theirs, tile, misere, (alpha, beta), nodes);
+        best = best.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+/// Finds the best tile for `sign` to play along with the number of moves
+/// searched, or `None` on a full board.
+fn search(board: &[Option<Sign>], sign: Sign, misere: bool) -> (Option<usize>, u32) {
+    let tiles_of = |of: Sign| {
+        (0..9)
+            .filter(|&index| board[index] == Some(of))
+            .fold(0u16, |tiles, index| tiles | (1 << index))
+    };
+    let other = if sign == Sign::X { Sign::O } else { Sign::X };
+    let (mine, theirs) = (tiles_of(sign), tiles_of(other));
+
+    let mut nodes = 0;
+    let mut best: Option<(usize, i8)> = None;
+    for tile in empty_tiles(mine | theirs) {
+        let alpha = best.map_or(-1, |(_, score)| score);
+        let score = score_move(mine, theirs, tile, misere, (alpha, 1), &mut nodes);
+        if best.map_or(true, |(_, best)| score > best) {
+            best = Some((tile, score));
+        }
+    }
+    (best.map(|(tile, _)| tile), nodes)
+}
+
+/// Index into a row-major 3x3 board of the best tile for `sign` to play, or
+/// `None` on a full board.
+pub fn best_move(board: &[Option<Sign>], sign: Sign, misere: bool) -> Option<usize> {
+    search(board, sign, misere).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays every possible human game against the program, returning
+    /// whether the human ever wins and the most moves any single search
+    /// looked at.
+    fn play_out(board: &mut Vec<Option<Sign>>, misere: bool) -> (bool, u32) {
+        let mut human_wins = false;
+        let mut most_nodes = 0;
+        for tile in 0..9 {
+            if board[tile].is_some() {
+                continue;
+            }
+            board[tile] = Some(Sign::X);
+            let x = bits(board, Sign::X);
+            if has_line(x) {
+                human_wins |= !misere;
+            } else if board.iter().any(Option::is_none) {
+                let (reply, nodes) = search(board, Sign::O, misere);
+                let reply = reply.unwrap();
+                most_nodes = most_nodes.max(nodes);
+                board[reply] = Some(Sign::O);
+                if has_line(bits(board, Sign::O)) {
+                    human_wins |= misere;
+                } else {
+                    let (wins, nodes) = play_out(board, misere);
+                    human_wins |= wins;
+                    most_nodes = most_nodes.max(nodes);
+                }
+                board[reply] = None;
+            }
+            board[tile] = None;
+        }
+        (human_wins, most_nodes)
+    }
+
+    fn bits(board: &[Option<Sign>], sign: Sign) -> u16 {
+        (0..9)
+            .filter(|&index| board[index] == Some(sign))
+            .fold(0, |tiles, index| tiles | (1 << index))
+    }
+
+    #[test]
+    fn test_program_never_loses() {
+        let (human_wins, most_nodes) = play_out(&mut vec![None; 9], false);
+        assert!(!human_wins);
+        // Bounds the moves searched for any one reply, the compute units
+        // they take on-chain are checked by the TS tests
+        assert!(most_nodes < 1_000);
+    }
+
+    #[test]
+    fn test_takes_the_win_and_blocks() {
+        // O to move can win on the top row or has to block X's middle row
+        let (x, o) = (Some(Sign::X), Some(Sign::O));
+        let board = [o, o, None, x, x, None, x, None, None];
+        assert_eq!(best_move(&board, Sign::O, false), Some(2));
+        let board = [o, None, None, x, x, None, None, None, None];
+        assert_eq!(best_move(&board, Sign::O, false), Some(5));
+    }
+
+    #[test]
+    fn test_misere_avoids_lines() {
+        let (x, o) = (Some(Sign::X), Some(Sign::O));
+        // Taking the top right corner would complete a line for O
+        let board = [o, o, None, x, None, None, x, None, None];
+        assert_ne!(best_move(&board, Sign::O, true), Some(2));
+    }
+}
@@ -0,0 +1,129 @@
+//! Integer-only Elo rating math. There is no floating point on BPF, so
+//! scores and probabilities are fixed-point values scaled by `SCALE`.
+
+pub const INITIAL_RATING: u32 = 1200;
+
+/// Fixed-point scale of scores and expected scores
+pub const SCALE: i64 = 1_000_000;
+
+pub const WIN: i64 = SCALE;
+pub const TIE: i64 = SCALE / 2;
+pub const LOSS: i64 = 0;
+
+/// Rating differences are capped, beyond this the stronger player is
+/// expected to win 99% of the time.
+const MAX_RATING_DIFFERENCE: i64 = 800;
+
+/// Internal scale for the exponential, so rounding errors stay well below
+/// the precision of `SCALE`.
+const EXP_SCALE: i128 = 1_000_000_000_000;
+
+/// ln(10) / 400 scaled by `EXP_SCALE`
+const LN_10_OVER_400: i128 = 5_756_462_732;
+
+/// e^x for `x >= 0`, both scaled by `EXP_SCALE`, summing the Taylor series
+/// until the terms vanish.
+fn exp(x: i128) -> i128 {
+    let mut term = EXP_SCALE;
+    let mut sum = EXP_SCALE;
+    let mut n = 1;
+    while term > 0 {
+        term = term * x / (n * EXP_SCALE);
+        sum += term;
+        n += 1;
+    }
+    sum
+}
+
+/// Expected score of a player rated `rating` against `opponent`, which is
+/// 1 / (1 + 10^((opponent - rating) / 400)) scaled by `SCALE`.
+pub fn expected_score(rating: u32, opponent: u32) -> i64 {
+    let difference =
+        (opponent as i64 - rating as i64).clamp(-MAX_RATING_DIFFERENCE, MAX_RATING_DIFFERENCE);
+    let power = exp(difference.unsigned_abs() as i128 * LN_10_OVER_400);
+    let expected = (EXP_SCALE * SCALE as i128 / (EXP_SCALE + power)) as i64;
+    if difference >= 0 {
+        expected
+    } else {
+        SCALE - expected
+    }
+}
+
+/// Rating points gained (or lost when negative) by a player rated `rating`
+/// who scored `score` (`WIN`, `TIE` or `LOSS`) against `opponent`, rounded to
+/// the nearest point.
+pub fn rating_change(rating: u32, opponent: u32, score: i64, k_factor: u8) -> i32 {
+    let numerator = k_factor as i64 * (score - expected_score(rating, opponent));
+    ((numerator + numerator.signum() * SCALE / 2) / SCALE) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Expected score rounded to three decimals, as reference tables list it
+    fn expected_per_mille(rating: u32, opponent: u32) -> i64 {
+        (expected_score(rating, opponent) + 500) / 1000
+    }
+
+    #[test]
+    fn test_expected_score() {
+        // Worked example from https://en.wikipedia.org/wiki/Elo_rating_system
+        assert_eq!(expected_per_mille(1613, 1609), 506);
+        assert_eq!(expected_per_mille(1613, 1477), 686);
+        assert_eq!(expected_per_mille(1613, 1388), 785);
+        assert_eq!(expected_per_mille(1613, 1586), 539);
+        assert_eq!(expected_per_mille(1613, 1720), 351);
+
+        assert_eq!(expected_score(1500, 1500), SCALE / 2);
+        assert_eq!(expected_per_mille(1400, 1800), 91);
+        assert_eq!(expected_per_mille(1800, 1400), 909);
+    }
+
+    #[test]
+    fn test_expected_scores_add_up_to_one() {
+        for (rating, opponent) in [(1613, 1477), (1200, 1216), (2000, 1000), (0, 3000)] {
+            let sum = expected_score(rating, opponent) + expected_score(opponent, rating);
+            assert!(
+                (sum - SCALE).abs() <= 1,
+                "{} vs {}: {}",
+                rating,
+                opponent,
+                sum
+            );
+        }
+    }
+
+    #[test]
+    fn test_large_rating_differences_are_capped() {
+        // 1 / (1 + 10^2) = 0.0099
+        assert_eq!(expected_score(1000, 1800), 9_900);
+        assert_eq!(expected_score(1000, 2800), 9_900);
+        assert_eq!(expected_score(2800, 1000), SCALE - 9_900);
+    }
+
+    #[test]
+    fn test_rating_change() {
+        assert_eq!(rating_change(1200, 1200, WIN, 32), 16);
+        assert_eq!(rating_change(1200, 1200, LOSS, 32), -16);
+        assert_eq!(rating_change(1200, 1200, TIE, 32), 0);
+        assert_eq!(rating_change(1200, 1200, WIN, 16), 8);
+
+        // The games of the Wikipedia example, one at a time:
+        // 32 * (0 - 0.506) = -16.2
+        assert_eq!(rating_change(1613, 1609, LOSS, 32), -16);
+        // 32 * (0.5 - 0.686) = -6.0
+        assert_eq!(rating_change(1613, 1477, TIE, 32), -6);
+        // 32 * (1 - 0.785) = 6.9
+        assert_eq!(rating_change(1613, 1388, WIN, 32), 7);
+        // 32 * (1 - 0.539) = 14.8
+        assert_eq!(rating_change(1613, 1586, WIN, 32), 15);
+        // 32 * (0 - 0.351) = -11.2
+        assert_eq!(rating_change(1613, 1720, LOSS, 32), -11);
+
+        // An upset moves more points than the expected result
+        assert_eq!(rating_change(1400, 1800, WIN, 32), 29);
+        assert_eq!(rating_change(1800, 1400, WIN, 32), 3);
+        assert_eq!(rating_change(1800, 1400, TIE, 32), -13);
+    }
+}
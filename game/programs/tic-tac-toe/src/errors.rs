@@ -7,4 +7,62 @@ pub enum TicTacToeError {
     GameAlreadyOver,
     NotPlayersTurn,
     GameAlreadyStarted,
+    NoWager,
+    WagerAlreadyMatched,
+    WagerNotMatched,
+    GameNotOver,
+    NotAPlayer,
+    InvalidMoveTimeout,
+    NoMoveTimeout,
+    TimeoutNotReached,
+    WaitingForOpponent,
+    CannotJoinOwnGame,
+    InvalidKFactor,
+    NoDrawOffer,
+    CannotAcceptOwnDrawOffer,
+    NoTokenWager,
+    MissingTokenAccounts,
+    InvalidEscrow,
+    InvalidMint,
+    InvalidBoardSize,
+    InvalidWinLength,
+    WrongSubBoard,
+    SubBoardAlreadyDecided,
+    WrongGameMode,
+    NoRandomFirstPlayer,
+    AlreadyCommitted,
+    WaitingForCommitment,
+    AlreadyRevealed,
+    SecretMismatch,
+    FirstPlayerNotDecided,
+    InvalidClock,
+    NoClock,
+    OutOfTime,
+    AlreadyMigrated,
+    UnsupportedAgainstProgram,
+    NothingToTakeBack,
+    NoTakebackRequest,
+    CannotAcceptOwnTakeback,
+    TakebackAfterPayout,
+    MissingProfiles,
+    InvalidBestOf,
+    SeriesOver,
+    SeriesGameInProgress,
+    WrongSeriesGame,
+    RematchWithWager,
+    InvalidTournamentSize,
+    TournamentWager,
+    RegistrationClosed,
+    AlreadyRegistered,
+    TournamentNotStarted,
+    InvalidMatch,
+    MatchDecided,
+    AlreadyQueued,
+    QueueFull,
+    NotQueued,
+    NoMatchInQueue,
+    GameNotScored,
+    OwnedGame,
+    NotUpgradeAuthority,
+    TooManySeriesGames,
 }
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::state::game::{Game, GameState, Tile};
+
+#[event]
+pub struct GameStarted {
+    pub game: Pubkey,
+    /// The second seat is the default pubkey for an open challenge, which is
+    /// started again once someone joins
+    pub players: [Pubkey; 2],
+    pub wager: u64,
+    pub move_timeout: i64,
+    /// Set when the wager is in this SPL token rather than SOL
+    pub mint: Option<Pubkey>,
+}
+
+impl GameStarted {
+    /// Emitted whenever `game` becomes active, clients index games by it
+    pub fn new(game: &Account<Game>) -> Self {
+        GameStarted {
+            game: game.key(),
+            players: game.players(),
+            wager: game.wager(),
+            move_timeout: game.config().move_timeout,
+            mint: game.mint(),
+        }
+    }
+}
+
+#[event]
+pub struct MovePlayed {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub tile: Tile,
+    /// The cube layer of the tile, always 0 outside cube games
+    pub layer: u8,
+    pub turn: u8,
+}
+
+#[event]
+pub struct GameEnded {
+    pub game: Pubkey,
+    pub outcome: GameState,
+    pub payouts: [u64; 2],
+    pub rating_changes: [i32; 2],
+}
@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+
+use crate::{instructions::settlement::*, state::game::*};
+
+pub fn accept_draw(ctx: Context<AcceptDraw>) -> Result<()> {
+    ctx.accounts.game.accept_draw(ctx.accounts.player.key())?;
+    msg!("Player {} accepts the draw", ctx.accounts.player.key());
+    ctx.accounts.settlement.settle(&mut ctx.accounts.game)
+}
+
+#[derive(Accounts)]
+pub struct AcceptDraw<'info> {
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    pub player: Signer<'info>,
+    pub settlement: Settlement<'info>,
+}
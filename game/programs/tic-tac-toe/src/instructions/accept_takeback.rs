@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::TicTacToeError,
+    state::{game::*, player_profile::*},
+};
+
+pub fn accept_takeback(ctx: Context<AcceptTakeback>) -> Result<()> {
+    let game = &mut ctx.accounts.game;
+    let ended = !game.is_active();
+    let winner = game.state().winner();
+    let rating_changes = game.rating_changes();
+    game.accept_takeback(ctx.accounts.player.key(), Clock::get()?.unix_timestamp)?;
+
+    if ended {
+        // The move that is gone had ended the game, so its result comes back
+        // out of both profiles
+        let (Some(player_one_profile), Some(player_two_profile)) = (
+            &mut ctx.accounts.player_one_profile,
+            &mut ctx.accounts.player_two_profile,
+        ) else {
+            return err!(TicTacToeError::MissingProfiles);
+        };
+        let players = game.players();
+        require_keys_eq!(player_one_profile.player(), players[0]);
+        require_keys_eq!(player_two_profile.player(), players[1]);
+        player_one_profile.unrecord(winner, rating_changes[0]);
+        player_two_profile.unrecord(winner, rating_changes[1]);
+    }
+
+    msg!(
+        "Player {} lets the last move be taken back",
+        ctx.accounts.player.key()
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AcceptTakeback<'info> {
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    pub player: Signer<'info>,
+    // Only needed when the move being taken back ended the game
+    #[account(mut)]
+    pub player_one_profile: Option<Account<'info, PlayerProfile>>,
+    #[account(mut)]
+    pub player_two_profile: Option<Account<'info, PlayerProfile>>,
+}
@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::GameStarted,
+    state::{game::*, tournament::*},
+};
+
+/// Reads the outcome of a match's game into the bracket. A tie is replayed
+/// in the same game account with the players swapping X and O. Anyone may
+/// send this.
+pub fn advance(ctx: Context<Advance>, index: u8) -> Result<()> {
+    let tournament = &mut ctx.accounts.tournament;
+    let game = &mut ctx.accounts.game;
+    if tournament.record(index, game.state())? {
+        let [player_one, player_two] = game.players();
+        let payer = game.payer();
+        game.start(
+            payer,
+            player_two,
+            Some(player_one),
+            *tournament.config(),
+            None,
+            Clock::get()?.unix_timestamp,
+        )?;
+        emit!(GameStarted::new(game));
+        msg!("Match {} was a tie and is replayed", index);
+    } else {
+        game.mark_scored();
+        if let Some(winner) = tournament.winner() {
+            msg!("Player {} wins the tournament", winner);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct Advance<'info> {
+    #[account(mut)]
+    pub tournament: Account<'info, Tournament>,
+    #[account(
+        mut,
+        seeds = [GAME_SEED, tournament.key().as_ref(), &[tournament.round()], &[index]],
+        bump
+    )]
+    pub game: Account<'info, Game>,
+}
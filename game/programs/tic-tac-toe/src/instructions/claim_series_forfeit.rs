@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+use crate::state::series::*;
+
+pub fn claim_series_forfeit(ctx: Context<ClaimSeriesForfeit>) -> Result<()> {
+    let series = &mut ctx.accounts.series;
+    let player = ctx.accounts.player.key();
+    series.claim_forfeit(player, Clock::get()?.unix_timestamp)?;
+    msg!(
+        "Player {} wins game {} of the series by forfeit",
+        player,
+        series.games_played()
+    );
+    if let Some(winner) = series.winner() {
+        msg!("Player {} wins the series", winner);
+    }
+    Ok(())
+}
+
+/// Sent by the player due to play O in the next game when the player due to
+/// play X doesn't start it
+#[derive(Accounts)]
+pub struct ClaimSeriesForfeit<'info> {
+    #[account(mut)]
+    pub series: Account<'info, Series>,
+    pub player: Signer<'info>,
+}
@@ -0,0 +1,19 @@
+use anchor_lang::prelude::*;
+
+use crate::{instructions::settlement::*, state::game::*};
+
+pub fn claim_timeout(ctx: Context<ClaimTimeout>) -> Result<()> {
+    ctx.accounts
+        .game
+        .claim_timeout(ctx.accounts.player.key(), Clock::get()?.unix_timestamp)?;
+    msg!("Player {} wins on timeout", ctx.accounts.player.key());
+    ctx.accounts.settlement.settle(&mut ctx.accounts.game)
+}
+
+#[derive(Accounts)]
+pub struct ClaimTimeout<'info> {
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    pub player: Signer<'info>,
+    pub settlement: Settlement<'info>,
+}
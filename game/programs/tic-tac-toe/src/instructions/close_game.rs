@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+use crate::{errors::TicTacToeError, instructions::token_wager::*, state::game::*};
+
+pub fn close_game(ctx: Context<CloseGame>) -> Result<()> {
+    if ctx.accounts.game.mint().is_some() {
+        ctx.accounts.close_escrow()?;
+    }
+    msg!("Game ID closed {}", ctx.accounts.game.key());
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CloseGame<'info> {
+    #[account(
+        mut,
+        close = payer,
+        // Player one can call off a game whose wager player two never
+        // matched and get their deposit back
+        constraint = !game.is_active() || !game.is_wager_settled() @ TicTacToeError::GameNotOver,
+        // The series or tournament would be left waiting on a game that is gone
+        constraint = game.is_scored() @ TicTacToeError::GameNotScored
+    )]
+    pub game: Account<'info, Game>,
+    #[account(mut, address = game.payer())]
+    pub payer: Signer<'info>,
+    // The remaining accounts close the escrow of a token wager and are left
+    // out for SOL wagers
+    #[account(mut)]
+    pub escrow: Option<Account<'info, TokenAccount>>,
+    /// CHECK: signs for the escrow, the escrow is checked against the game in `close_escrow`
+    pub escrow_authority: Option<UncheckedAccount<'info>>,
+    /// Refunded with the wager of an open challenge nobody joined, or of a
+    /// game player two never matched
+    #[account(mut, token::authority = payer)]
+    pub payer_token_account: Option<Account<'info, TokenAccount>>,
+    pub mint: Option<Account<'info, Mint>>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+impl<'info> CloseGame<'info> {
+    /// Refunds anything left in the token escrow and returns its rent to the
+    /// payer
+    fn close_escrow(&self) -> Result<()> {
+        let (Some(escrow), Some(escrow_authority), Some(token_program)) =
+            (&self.escrow, &self.escrow_authority, &self.token_program)
+        else {
+            return err!(TicTacToeError::MissingTokenAccounts);
+        };
+        let bump = check_escrow(&self.game, &escrow.key())?;
+        let game_key = self.game.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[ESCROW_SEED, game_key.as_ref(), &[bump]]];
+        if escrow.amount > 0 {
+            let (Some(payer_token_account), Some(mint)) = (&self.payer_token_account, &self.mint)
+            else {
+                return err!(TicTacToeError::MissingTokenAccounts);
+            };
+            token::transfer_checked(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    token::TransferChecked {
+                        from: escrow.to_account_info(),
+                        mint: mint.to_account_info(),
+                        to: payer_token_account.to_account_info(),
+                        authority: escrow_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                escrow.amount,
+                mint.decimals,
+            )?;
+        }
+        token::close_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            token::CloseAccount {
+                account: escrow.to_account_info(),
+                destination: self.payer.to_account_info(),
+                authority: escrow_authority.to_account_info(),
+            },
+            signer_seeds,
+        ))
+    }
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::state::game::*;
+
+pub fn commit_secret(ctx: Context<CommitSecret>, commitment: [u8; 32]) -> Result<()> {
+    ctx.accounts.game.commit_secret(
+        ctx.accounts.player.key(),
+        commitment,
+        Clock::get()?.unix_timestamp,
+    )?;
+    msg!("Player {} commits to a secret", ctx.accounts.player.key());
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CommitSecret<'info> {
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    pub player: Signer<'info>,
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{game::GameConfig, queue::*};
+
+pub fn create_queue(ctx: Context<CreateQueue>, id: u64, config: GameConfig) -> Result<()> {
+    ctx.accounts.queue.start(config)?;
+    msg!(
+        "Queue {} opens for games with a wager of {} lamports",
+        id,
+        config.wager
+    );
+    Ok(())
+}
+
+/// Queues are PDAs of an id so clients can find them without being told
+/// their address
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateQueue<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = Queue::MAXIMUM_SIZE + 8,
+        seeds = [QUEUE_SEED, &id.to_le_bytes()],
+        bump
+    )]
+    pub queue: Account<'info, Queue>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
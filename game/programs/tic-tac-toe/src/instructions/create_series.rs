@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{game::GameConfig, series::*};
+
+pub fn create_series(
+    ctx: Context<CreateSeries>,
+    player_two: Pubkey,
+    best_of: u8,
+    config: GameConfig,
+) -> Result<()> {
+    let player_one = ctx.accounts.player_one.key();
+    ctx.accounts.series.start(
+        [player_one, player_two],
+        best_of,
+        config,
+        Clock::get()?.unix_timestamp,
+    )?;
+    msg!(
+        "Best of {} series between {} and {}",
+        best_of,
+        player_one,
+        player_two
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreateSeries<'info> {
+    #[account(init, payer = player_one, space = Series::MAXIMUM_SIZE + 8)]
+    pub series: Account<'info, Series>,
+    #[account(mut)]
+    pub player_one: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
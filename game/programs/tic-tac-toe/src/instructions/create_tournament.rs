@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{game::GameConfig, tournament::*};
+
+pub fn create_tournament(
+    ctx: Context<CreateTournament>,
+    size: u8,
+    config: GameConfig,
+) -> Result<()> {
+    ctx.accounts
+        .tournament
+        .start(ctx.accounts.authority.key(), size, config)?;
+    msg!("Tournament for {} players opens registration", size);
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(size: u8)]
+pub struct CreateTournament<'info> {
+    #[account(init, payer = authority, space = Tournament::maximum_size(size) + 8)]
+    pub tournament: Account<'info, Tournament>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
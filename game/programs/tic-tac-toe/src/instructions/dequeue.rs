@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::state::queue::*;
+
+/// Leaves the queue, refunding the wager deposited when joining it
+pub fn dequeue(ctx: Context<Dequeue>) -> Result<()> {
+    let queue = &mut ctx.accounts.queue;
+    queue.dequeue(ctx.accounts.player.key())?;
+    let wager = queue.config().wager;
+    if wager > 0 {
+        queue.sub_lamports(wager)?;
+        ctx.accounts.player.add_lamports(wager)?;
+    }
+    msg!("Player {} leaves the queue", ctx.accounts.player.key());
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Dequeue<'info> {
+    #[account(mut)]
+    pub queue: Account<'info, Queue>,
+    #[account(mut)]
+    pub player: Signer<'info>,
+}
@@ -0,0 +1,41 @@
+use anchor_lang::{prelude::*, system_program};
+
+use crate::state::{player_profile::*, queue::*};
+
+pub fn enqueue(ctx: Context<Enqueue>, band: Option<u32>) -> Result<()> {
+    let rating = PlayerProfile::rating_of(&ctx.accounts.profile)?;
+    let queue = &mut ctx.accounts.queue;
+    queue.enqueue(ctx.accounts.player.key(), rating, band)?;
+    let wager = queue.config().wager;
+    if wager > 0 {
+        // Held by the queue until the player is paired or leaves it
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.player.to_account_info(),
+                    to: ctx.accounts.queue.to_account_info(),
+                },
+            ),
+            wager,
+        )?;
+    }
+    msg!(
+        "Player {} joins the queue with a rating of {}",
+        ctx.accounts.player.key(),
+        rating
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Enqueue<'info> {
+    #[account(mut)]
+    pub queue: Account<'info, Queue>,
+    #[account(mut)]
+    pub player: Signer<'info>,
+    /// CHECK: the player's profile, read for their rating if it exists
+    #[account(seeds = [PROFILE_SEED, player.key().as_ref()], bump)]
+    pub profile: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
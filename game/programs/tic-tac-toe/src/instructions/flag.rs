@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+
+use crate::{instructions::settlement::*, state::game::*};
+
+pub fn flag(ctx: Context<Flag>) -> Result<()> {
+    ctx.accounts.game.flag(Clock::get()?.unix_timestamp)?;
+    msg!("Game {} lost on time", ctx.accounts.game.key());
+    ctx.accounts.settlement.settle(&mut ctx.accounts.game)
+}
+
+/// Anyone can flag a game, the settlement's payer only pays for profiles
+#[derive(Accounts)]
+pub struct Flag<'info> {
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    pub settlement: Settlement<'info>,
+}
@@ -0,0 +1,34 @@
+use anchor_lang::{prelude::*, solana_program::bpf_loader_upgradeable};
+
+use crate::{errors::TicTacToeError, state::config::*};
+
+pub fn initialize_config(ctx: Context<InitializeConfig>, k_factor: u8) -> Result<()> {
+    ctx.accounts
+        .config
+        .set(ctx.accounts.authority.key(), k_factor)
+}
+
+/// Only whoever can upgrade the program gets to create its config, otherwise
+/// the first caller on a fresh deployment would own it
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Config::MAXIMUM_SIZE + 8,
+        seeds = [CONFIG_SEED],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ TicTacToeError::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
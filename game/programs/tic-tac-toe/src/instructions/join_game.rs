@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::{events::GameStarted, instructions::token_wager::*, state::game::*};
+
+pub fn join_game(ctx: Context<JoinGame>) -> Result<()> {
+    ctx.accounts
+        .game
+        .join(ctx.accounts.player_two.key(), Clock::get()?.unix_timestamp)?;
+    if ctx.accounts.game.wager() > 0 {
+        ctx.accounts.token_wager.deposit(
+            &ctx.accounts.game,
+            &ctx.accounts.player_two,
+            &ctx.accounts.system_program,
+        )?;
+    }
+    emit!(GameStarted::new(&ctx.accounts.game));
+    msg!(
+        "Player {} joins game {}",
+        ctx.accounts.player_two.key(),
+        ctx.accounts.game.key()
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct JoinGame<'info> {
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    #[account(mut)]
+    pub player_two: Signer<'info>,
+    pub token_wager: TokenWager<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{player_profile::*, tournament::*};
+
+pub fn join_tournament(ctx: Context<JoinTournament>) -> Result<()> {
+    let rating = PlayerProfile::rating_of(&ctx.accounts.profile)?;
+    let tournament = &mut ctx.accounts.tournament;
+    tournament.register(ctx.accounts.player.key(), rating)?;
+    msg!(
+        "Player {} joins the tournament with a rating of {}",
+        ctx.accounts.player.key(),
+        rating
+    );
+    if tournament.round() > 0 {
+        msg!("The bracket is full and has been drawn");
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct JoinTournament<'info> {
+    #[account(mut)]
+    pub tournament: Account<'info, Tournament>,
+    pub player: Signer<'info>,
+    /// CHECK: the player's profile, read for their rating if it exists
+    #[account(seeds = [PROFILE_SEED, player.key().as_ref()], bump)]
+    pub profile: UncheckedAccount<'info>,
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::{instructions::token_wager::*, state::game::*};
+
+pub fn match_wager(ctx: Context<MatchWager>) -> Result<()> {
+    ctx.accounts
+        .game
+        .match_wager(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.token_wager.deposit(
+        &ctx.accounts.game,
+        &ctx.accounts.player_two,
+        &ctx.accounts.system_program,
+    )
+}
+
+#[derive(Accounts)]
+pub struct MatchWager<'info> {
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    #[account(mut, address = game.players()[1])]
+    pub player_two: Signer<'info>,
+    pub token_wager: TokenWager<'info>,
+    pub system_program: Program<'info, System>,
+}
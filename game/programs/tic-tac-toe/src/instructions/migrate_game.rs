@@ -0,0 +1,48 @@
+use anchor_lang::{prelude::*, system_program, Discriminator};
+
+use crate::state::game::*;
+
+/// Rewrites a game stored in an older layout in the latest one, growing the
+/// account as needed. Anyone can migrate a game, `payer` tops up its rent.
+pub fn migrate_game(ctx: Context<MigrateGame>) -> Result<()> {
+    let game = ctx.accounts.game.to_account_info();
+    let migrated = {
+        let data = game.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == Game::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        Game::migrate(&data[8..])?
+    };
+
+    let space = (Game::maximum_size(migrated.config()) + 8).max(game.data_len());
+    let rent = Rent::get()?.minimum_balance(space);
+    if rent > game.lamports() {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: game.clone(),
+                },
+            ),
+            rent - game.lamports(),
+        )?;
+    }
+    game.realloc(space, false)?;
+    migrated.try_serialize(&mut &mut game.try_borrow_mut_data()?[..])?;
+
+    msg!("Game {} migrated to version {}", game.key(), Game::VERSION);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MigrateGame<'info> {
+    /// CHECK: read and written by hand since it may not be in the current
+    /// layout, the discriminator is checked in `migrate_game`
+    #[account(mut, owner = crate::ID)]
+    pub game: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
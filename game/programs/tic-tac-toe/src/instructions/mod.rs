@@ -1,5 +1,69 @@
+pub use accept_draw::*;
+pub use accept_takeback::*;
+pub use advance::*;
+pub use claim_series_forfeit::*;
+pub use claim_timeout::*;
+pub use close_game::*;
+pub use commit_secret::*;
+pub use create_queue::*;
+pub use create_series::*;
+pub use create_tournament::*;
+pub use dequeue::*;
+pub use enqueue::*;
+pub use flag::*;
+pub use initialize_config::*;
+pub use join_game::*;
+pub use join_tournament::*;
+pub use match_wager::*;
+pub use migrate_game::*;
+pub use offer_draw::*;
+pub use pair_players::*;
 pub use play::*;
+pub use play_cube::*;
+pub use rematch::*;
+pub use request_takeback::*;
+pub use resign::*;
+pub use reveal_secret::*;
+pub use settlement::*;
 pub use setup_game::*;
+pub use setup_pda_game::*;
+pub use start_match::*;
+pub use start_next_game::*;
+pub use token_wager::*;
+pub use update_config::*;
+pub use update_series::*;
 
+pub mod accept_draw;
+pub mod accept_takeback;
+pub mod advance;
+pub mod claim_series_forfeit;
+pub mod claim_timeout;
+pub mod close_game;
+pub mod commit_secret;
+pub mod create_queue;
+pub mod create_series;
+pub mod create_tournament;
+pub mod dequeue;
+pub mod enqueue;
+pub mod flag;
+pub mod initialize_config;
+pub mod join_game;
+pub mod join_tournament;
+pub mod match_wager;
+pub mod migrate_game;
+pub mod offer_draw;
+pub mod pair_players;
 pub mod play;
+pub mod play_cube;
+pub mod rematch;
+pub mod request_takeback;
+pub mod resign;
+pub mod reveal_secret;
+pub mod settlement;
 pub mod setup_game;
+pub mod setup_pda_game;
+pub mod start_match;
+pub mod start_next_game;
+pub mod token_wager;
+pub mod update_config;
+pub mod update_series;
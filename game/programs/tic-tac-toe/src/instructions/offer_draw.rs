@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+use crate::state::game::*;
+
+pub fn offer_draw(ctx: Context<OfferDraw>) -> Result<()> {
+    ctx.accounts.game.offer_draw(ctx.accounts.player.key())?;
+    msg!("Player {} offers a draw", ctx.accounts.player.key());
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct OfferDraw<'info> {
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    pub player: Signer<'info>,
+}
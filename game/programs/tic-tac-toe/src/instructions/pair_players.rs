@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::GameStarted,
+    state::{game::*, queue::*},
+};
+
+/// Sets up a game between the first two compatible players in the queue and
+/// moves their deposits into it. Anyone may send this, `payer` pays the
+/// game's rent and gets it back when closing the game.
+pub fn pair_players(ctx: Context<PairPlayers>) -> Result<()> {
+    let queue = &mut ctx.accounts.queue;
+    let [player_one, player_two] = queue.pair()?;
+    let config = *queue.config();
+    let now = Clock::get()?.unix_timestamp;
+    let game = &mut ctx.accounts.game;
+    game.start(
+        ctx.accounts.payer.key(),
+        player_one,
+        Some(player_two),
+        config,
+        None,
+        now,
+    )?;
+    if config.wager > 0 {
+        queue.sub_lamports(2 * config.wager)?;
+        game.add_lamports(2 * config.wager)?;
+        game.match_wager(now)?;
+    }
+    emit!(GameStarted::new(game));
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PairPlayers<'info> {
+    #[account(mut)]
+    pub queue: Account<'info, Queue>,
+    #[account(
+        init,
+        payer = payer,
+        space = Game::maximum_size(queue.config()) + 8,
+        seeds = [GAME_SEED, queue.key().as_ref(), &queue.games_created().to_le_bytes()],
+        bump
+    )]
+    pub game: Account<'info, Game>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
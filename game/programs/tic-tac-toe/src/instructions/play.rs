@@ -1,18 +1,51 @@
 use anchor_lang::prelude::*;
 
-use crate::{errors::TicTacToeError, state::game::*};
+use crate::{
+    errors::TicTacToeError, events::MovePlayed, instructions::settlement::*, state::game::*,
+};
 
 pub fn play(ctx: Context<Play>, tile: Tile) -> Result<()> {
-    let game = &mut ctx.accounts.game;
+    ctx.accounts.play_move(|game, now| game.play(&tile, now))
+}
+
+impl<'info> Play<'info> {
+    /// Checks it is the signer's turn, makes the move with `make_move` and
+    /// settles the game if the move ended it.
+    pub(crate) fn play_move(
+        &mut self,
+        make_move: impl FnOnce(&mut Game, i64) -> Result<()>,
+    ) -> Result<()> {
+        let game = &mut self.game;
+
+        let current_player = game.current_player()?;
+        require!(game.is_active(), TicTacToeError::GameAlreadyOver);
+        require_keys_eq!(
+            current_player,
+            self.player.key(),
+            TicTacToeError::NotPlayersTurn
+        );
+
+        let turn = game.turn();
+        let moves_before = game.moves().len();
+        make_move(game, Clock::get()?.unix_timestamp)?;
+        // A game against the program also gets the program's reply
+        let players = game.players();
+        for (i, new_move) in game.moves()[moves_before..].iter().enumerate() {
+            emit!(MovePlayed {
+                game: game.key(),
+                player: players[new_move.player as usize],
+                tile: new_move.tile.clone(),
+                layer: new_move.layer,
+                turn: turn + i as u8,
+            });
+        }
 
-    require!(game.is_active(), TicTacToeError::GameAlreadyOver);
-    require_keys_eq!(
-        game.current_player(),
-        ctx.accounts.player.key(),
-        TicTacToeError::NotPlayersTurn
-    );
+        if !game.is_active() {
+            self.settlement.settle(&mut self.game)?;
+        }
 
-    game.play(&tile)
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
@@ -20,4 +53,5 @@ pub struct Play<'info> {
     #[account(mut)]
     pub game: Account<'info, Game>,
     pub player: Signer<'info>,
+    pub settlement: Settlement<'info>,
 }
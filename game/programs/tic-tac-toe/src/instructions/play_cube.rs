@@ -0,0 +1,8 @@
+use anchor_lang::prelude::*;
+
+use crate::{instructions::play::*, state::game::*};
+
+pub fn play_cube(ctx: Context<Play>, tile: CubeTile) -> Result<()> {
+    ctx.accounts
+        .play_move(|game, now| game.play_cube(&tile, now))
+}
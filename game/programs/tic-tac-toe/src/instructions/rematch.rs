@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+use crate::{events::GameStarted, state::game::*};
+
+pub fn rematch(ctx: Context<Rematch>) -> Result<()> {
+    let game = &mut ctx.accounts.game;
+    let player = ctx.accounts.player.key();
+    if game.rematch(player, Clock::get()?.unix_timestamp)? {
+        msg!("Player {} accepts the rematch", player);
+        emit!(GameStarted::new(game));
+    } else {
+        msg!("Player {} offers a rematch", player);
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Rematch<'info> {
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    pub player: Signer<'info>,
+}
@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+use crate::state::game::*;
+
+pub fn request_takeback(ctx: Context<RequestTakeback>) -> Result<()> {
+    ctx.accounts
+        .game
+        .request_takeback(ctx.accounts.player.key())?;
+    msg!(
+        "Player {} asks to take back their move",
+        ctx.accounts.player.key()
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RequestTakeback<'info> {
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    pub player: Signer<'info>,
+}
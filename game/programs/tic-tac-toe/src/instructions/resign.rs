@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+
+use crate::{instructions::settlement::*, state::game::*};
+
+pub fn resign(ctx: Context<Resign>) -> Result<()> {
+    ctx.accounts.game.resign(ctx.accounts.player.key())?;
+    msg!("Player {} resigns", ctx.accounts.player.key());
+    ctx.accounts.settlement.settle(&mut ctx.accounts.game)
+}
+
+#[derive(Accounts)]
+pub struct Resign<'info> {
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    pub player: Signer<'info>,
+    pub settlement: Settlement<'info>,
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::state::game::*;
+
+pub fn reveal_secret(ctx: Context<RevealSecret>, secret: [u8; 32]) -> Result<()> {
+    ctx.accounts.game.reveal_secret(
+        ctx.accounts.player.key(),
+        secret,
+        Clock::get()?.unix_timestamp,
+    )?;
+    msg!("Player {} reveals their secret", ctx.accounts.player.key());
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RevealSecret<'info> {
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+    pub player: Signer<'info>,
+}
@@ -0,0 +1,157 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+use crate::{
+    elo,
+    errors::TicTacToeError,
+    events::GameEnded,
+    instructions::token_wager::*,
+    state::{config::*, game::*, player_profile::*},
+};
+
+/// Accounts that are paid out and credited with the result when a game ends.
+#[derive(Accounts)]
+pub struct Settlement<'info> {
+    /// CHECK: only credited with lamports, checked against the game's players in `settle`
+    #[account(mut)]
+    pub player_one: UncheckedAccount<'info>,
+    /// CHECK: only credited with lamports, checked against the game's players in `settle`
+    #[account(mut)]
+    pub player_two: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PlayerProfile::MAXIMUM_SIZE + 8,
+        seeds = [PROFILE_SEED, player_one.key().as_ref()],
+        bump
+    )]
+    pub player_one_profile: Account<'info, PlayerProfile>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PlayerProfile::MAXIMUM_SIZE + 8,
+        seeds = [PROFILE_SEED, player_two.key().as_ref()],
+        bump
+    )]
+    pub player_two_profile: Account<'info, PlayerProfile>,
+    /// CHECK: the program's config, read by `Config::k_factor_of` as it may not exist yet
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: UncheckedAccount<'info>,
+    /// Pays for any profile that doesn't exist yet
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    // The remaining accounts pay out token wagers and are left out for SOL
+    // wagers
+    pub mint: Option<Account<'info, Mint>>,
+    #[account(mut)]
+    pub escrow: Option<Account<'info, TokenAccount>>,
+    /// CHECK: signs for the escrow, the escrow is checked against the game in `settle`
+    pub escrow_authority: Option<UncheckedAccount<'info>>,
+    #[account(mut, token::authority = player_one)]
+    pub player_one_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, token::authority = player_two)]
+    pub player_two_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+impl<'info> Settlement<'info> {
+    pub fn settle(&mut self, game: &mut Account<'info, Game>) -> Result<()> {
+        let players = game.players();
+        require_keys_eq!(self.player_one.key(), players[0]);
+        require_keys_eq!(self.player_two.key(), players[1]);
+
+        let payouts = game.payouts();
+        if game.mint().is_some() {
+            self.pay_out_tokens(game, payouts)?;
+        } else {
+            for (player, amount) in [&self.player_one, &self.player_two]
+                .into_iter()
+                .zip(payouts)
+            {
+                if amount > 0 {
+                    game.sub_lamports(amount)?;
+                    player.add_lamports(amount)?;
+                }
+            }
+        }
+
+        self.player_one_profile.initialize(players[0]);
+        self.player_two_profile.initialize(players[1]);
+        // Player two never put their wager in, so neither of them played
+        let mut rating_changes = [0, 0];
+        if game.is_wager_settled() {
+            let ratings = [
+                self.player_one_profile.rating(),
+                self.player_two_profile.rating(),
+            ];
+            let winner = game.state().winner();
+            let scores = players.map(|player| match winner {
+                Some(winner) if winner == player => elo::WIN,
+                Some(_) => elo::LOSS,
+                None => elo::TIE,
+            });
+            let k_factor = Config::k_factor_of(&self.config)?;
+            rating_changes = [
+                elo::rating_change(ratings[0], ratings[1], scores[0], k_factor),
+                elo::rating_change(ratings[1], ratings[0], scores[1], k_factor),
+            ];
+
+            self.player_one_profile.record(winner, rating_changes[0]);
+            self.player_two_profile.record(winner, rating_changes[1]);
+            game.record_rating_changes(rating_changes);
+        }
+
+        emit!(GameEnded {
+            game: game.key(),
+            outcome: game.state().clone(),
+            payouts,
+            rating_changes,
+        });
+        Ok(())
+    }
+
+    fn pay_out_tokens(&self, game: &Account<'info, Game>, payouts: [u64; 2]) -> Result<()> {
+        let (Some(mint), Some(escrow), Some(escrow_authority), Some(token_program)) = (
+            &self.mint,
+            &self.escrow,
+            &self.escrow_authority,
+            &self.token_program,
+        ) else {
+            return err!(TicTacToeError::MissingTokenAccounts);
+        };
+        let bump = check_escrow(game, &escrow.key())?;
+        let game_key = game.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[ESCROW_SEED, game_key.as_ref(), &[bump]]];
+
+        for (token_account, amount) in [
+            &self.player_one_token_account,
+            &self.player_two_token_account,
+        ]
+        .into_iter()
+        .zip(payouts)
+        {
+            if amount == 0 {
+                continue;
+            }
+            let token_account = token_account
+                .as_ref()
+                .ok_or(TicTacToeError::MissingTokenAccounts)?;
+            token::transfer_checked(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    token::TransferChecked {
+                        from: escrow.to_account_info(),
+                        mint: mint.to_account_info(),
+                        to: token_account.to_account_info(),
+                        authority: escrow_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+                mint.decimals,
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,20 +1,61 @@
 use anchor_lang::prelude::*;
 
-use crate::state::game::*;
+use crate::{events::GameStarted, instructions::token_wager::*, state::game::*};
 
-pub fn setup_game(ctx: Context<SetupGame>, player_two: Pubkey) -> Result<()> {
-    ctx.accounts
-        .game
-        .start([ctx.accounts.player_one.key(), player_two])?;
-    msg!("Game ID started {}", ctx.accounts.game.key());
+pub fn setup_game(
+    ctx: Context<SetupGame>,
+    player_two: Option<Pubkey>,
+    config: GameConfig,
+) -> Result<()> {
+    start_game(
+        &mut ctx.accounts.game,
+        &ctx.accounts.player_one,
+        player_two,
+        config,
+        &ctx.accounts.token_wager,
+        &ctx.accounts.system_program,
+    )
+}
+
+/// Starts a freshly allocated game and escrows player one's wager, shared by
+/// every instruction that creates a game.
+pub(crate) fn start_game<'info>(
+    game: &mut Account<'info, Game>,
+    player_one: &Signer<'info>,
+    player_two: Option<Pubkey>,
+    config: GameConfig,
+    token_wager: &TokenWager<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    game.start(
+        player_one.key(),
+        player_one.key(),
+        player_two,
+        config,
+        token_wager.mint(),
+        Clock::get()?.unix_timestamp,
+    )?;
+    if game.mint().is_some() {
+        token_wager.create_escrow(game, player_one, system_program)?;
+    }
+    if config.wager > 0 {
+        token_wager.deposit(game, player_one, system_program)?;
+    }
+    emit!(GameStarted::new(game));
     Ok(())
 }
 
 #[derive(Accounts)]
+#[instruction(player_two: Option<Pubkey>, config: GameConfig)]
 pub struct SetupGame<'info> {
-    #[account(init, payer = player_one, space = Game::MAXIMUM_SIZE + 8)]
+    #[account(
+        init,
+        payer = player_one,
+        space = Game::maximum_size(&config) + 8
+    )]
     pub game: Account<'info, Game>,
     #[account(mut)]
     pub player_one: Signer<'info>,
+    pub token_wager: TokenWager<'info>,
     pub system_program: Program<'info, System>,
 }
@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    instructions::{setup_game::start_game, token_wager::*},
+    state::game::*,
+};
+
+pub fn setup_pda_game(
+    ctx: Context<SetupPdaGame>,
+    player_two: Option<Pubkey>,
+    _nonce: u64,
+    config: GameConfig,
+) -> Result<()> {
+    start_game(
+        &mut ctx.accounts.game,
+        &ctx.accounts.player_one,
+        player_two,
+        config,
+        &ctx.accounts.token_wager,
+        &ctx.accounts.system_program,
+    )
+}
+
+/// Same as `SetupGame`, but the game lives at an address that clients can
+/// re-derive from the players and player one's nonce. Open challenges use
+/// the default pubkey in place of player two.
+#[derive(Accounts)]
+#[instruction(player_two: Option<Pubkey>, nonce: u64, config: GameConfig)]
+pub struct SetupPdaGame<'info> {
+    #[account(
+        init,
+        payer = player_one,
+        space = Game::maximum_size(&config) + 8,
+        seeds = [
+            GAME_SEED,
+            player_one.key().as_ref(),
+            player_two.unwrap_or_default().as_ref(),
+            &nonce.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub game: Account<'info, Game>,
+    #[account(mut)]
+    pub player_one: Signer<'info>,
+    pub token_wager: TokenWager<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::GameStarted,
+    state::{game::*, tournament::*},
+};
+
+/// Sets up the game of a match in the current round. Anyone may send this,
+/// `payer` pays the game's rent and gets it back when closing the game.
+pub fn start_match(ctx: Context<StartMatch>, index: u8) -> Result<()> {
+    let tournament = &ctx.accounts.tournament;
+    let [player_one, player_two] = tournament.match_players(index)?;
+    let game = &mut ctx.accounts.game;
+    game.start(
+        ctx.accounts.payer.key(),
+        player_one,
+        Some(player_two),
+        *tournament.config(),
+        None,
+        Clock::get()?.unix_timestamp,
+    )?;
+    // Closing the game before `advance` would let `start_match` set it up
+    // again and erase the result
+    game.set_owner(tournament.key());
+    emit!(GameStarted::new(game));
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct StartMatch<'info> {
+    pub tournament: Account<'info, Tournament>,
+    #[account(
+        init,
+        payer = payer,
+        space = Game::maximum_size(tournament.config()) + 8,
+        seeds = [GAME_SEED, tournament.key().as_ref(), &[tournament.round()], &[index]],
+        bump
+    )]
+    pub game: Account<'info, Game>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::TicTacToeError,
+    instructions::{setup_game::start_game, token_wager::*},
+    state::{game::*, series::*},
+};
+
+pub fn start_next_game(ctx: Context<StartNextGame>) -> Result<()> {
+    let series = &mut ctx.accounts.series;
+    let [player_one, player_two] = series.next_players()?;
+    require_keys_eq!(
+        ctx.accounts.player_one.key(),
+        player_one,
+        TicTacToeError::NotPlayersTurn
+    );
+    start_game(
+        &mut ctx.accounts.game,
+        &ctx.accounts.player_one,
+        Some(player_two),
+        *series.config(),
+        &ctx.accounts.token_wager,
+        &ctx.accounts.system_program,
+    )?;
+    ctx.accounts.game.set_owner(series.key());
+    series.start_game(ctx.accounts.game.key())?;
+    msg!("Game {} of the series starts", series.games_played());
+    Ok(())
+}
+
+/// Sets up the next game of a series, sent by whoever plays X in it
+#[derive(Accounts)]
+pub struct StartNextGame<'info> {
+    #[account(mut)]
+    pub series: Account<'info, Series>,
+    #[account(
+        init,
+        payer = player_one,
+        space = Game::maximum_size(series.config()) + 8,
+        seeds = [GAME_SEED, series.key().as_ref(), &series.games_played().to_le_bytes()],
+        bump
+    )]
+    pub game: Account<'info, Game>,
+    #[account(mut)]
+    pub player_one: Signer<'info>,
+    pub token_wager: TokenWager<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,146 @@
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address, AssociatedToken},
+    token::{self, Mint, Token, TokenAccount},
+};
+
+use crate::{errors::TicTacToeError, state::game::*};
+
+pub const ESCROW_SEED: &[u8] = b"escrow";
+
+/// The PDA that owns a game's token escrow, which is the associated token
+/// account of this address for the game's mint.
+pub fn escrow_authority(game: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ESCROW_SEED, game.as_ref()], &crate::ID)
+}
+
+/// Checks that `escrow` is the token escrow of `game` and returns the bump
+/// of its authority for signing transfers out of it.
+pub(crate) fn check_escrow(game: &Account<Game>, escrow: &Pubkey) -> Result<u8> {
+    let mint = game.mint().ok_or(TicTacToeError::NoTokenWager)?;
+    let (authority, bump) = escrow_authority(&game.key());
+    require_keys_eq!(
+        *escrow,
+        get_associated_token_address(&authority, &mint),
+        TicTacToeError::InvalidEscrow
+    );
+    Ok(bump)
+}
+
+/// Accounts for moving a player's SPL token wager into escrow. They are all
+/// left out for SOL wagers.
+#[derive(Accounts)]
+pub struct TokenWager<'info> {
+    pub mint: Option<Account<'info, Mint>>,
+    /// The depositing player's token account
+    #[account(mut)]
+    pub player_token_account: Option<Account<'info, TokenAccount>>,
+    /// CHECK: derived from the game and mint in `check_escrow`, created by
+    /// `create_escrow` when the game is set up
+    #[account(mut)]
+    pub escrow: Option<UncheckedAccount<'info>>,
+    /// CHECK: only needed to create the escrow, checked in `create_escrow`
+    pub escrow_authority: Option<UncheckedAccount<'info>>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+}
+
+impl<'info> TokenWager<'info> {
+    pub fn mint(&self) -> Option<Pubkey> {
+        self.mint.as_ref().map(|mint| mint.key())
+    }
+
+    /// Creates the associated token account that holds a new game's tokens.
+    /// The escrow of a PDA game can be worked out in advance, so one that
+    /// somebody else already created is used as is.
+    pub fn create_escrow(
+        &self,
+        game: &Account<'info, Game>,
+        payer: &Signer<'info>,
+        system_program: &Program<'info, System>,
+    ) -> Result<()> {
+        let (
+            Some(mint),
+            Some(escrow),
+            Some(authority),
+            Some(token_program),
+            Some(associated_token_program),
+        ) = (
+            &self.mint,
+            &self.escrow,
+            &self.escrow_authority,
+            &self.token_program,
+            &self.associated_token_program,
+        )
+        else {
+            return err!(TicTacToeError::MissingTokenAccounts);
+        };
+        require_keys_eq!(
+            authority.key(),
+            escrow_authority(&game.key()).0,
+            TicTacToeError::InvalidEscrow
+        );
+        check_escrow(game, &escrow.key())?;
+        associated_token::create_idempotent(CpiContext::new(
+            associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: payer.to_account_info(),
+                associated_token: escrow.to_account_info(),
+                authority: authority.to_account_info(),
+                mint: mint.to_account_info(),
+                system_program: system_program.to_account_info(),
+                token_program: token_program.to_account_info(),
+            },
+        ))
+    }
+
+    /// Moves `player`'s wager into the game, in tokens if the game has a
+    /// mint and in lamports otherwise.
+    pub fn deposit(
+        &self,
+        game: &Account<'info, Game>,
+        player: &Signer<'info>,
+        system_program: &Program<'info, System>,
+    ) -> Result<()> {
+        let amount = game.wager();
+        if game.mint().is_none() {
+            return system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: player.to_account_info(),
+                        to: game.to_account_info(),
+                    },
+                ),
+                amount,
+            );
+        }
+        let (Some(mint), Some(player_token_account), Some(escrow), Some(token_program)) = (
+            &self.mint,
+            &self.player_token_account,
+            &self.escrow,
+            &self.token_program,
+        ) else {
+            return err!(TicTacToeError::MissingTokenAccounts);
+        };
+        require_keys_eq!(
+            mint.key(),
+            game.mint().unwrap_or_default(),
+            TicTacToeError::InvalidMint
+        );
+        check_escrow(game, &escrow.key())?;
+        token::transfer_checked(
+            CpiContext::new(
+                token_program.to_account_info(),
+                token::TransferChecked {
+                    from: player_token_account.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: escrow.to_account_info(),
+                    authority: player.to_account_info(),
+                },
+            ),
+            amount,
+            mint.decimals,
+        )
+    }
+}
@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+
+use crate::state::config::*;
+
+pub fn update_config(ctx: Context<UpdateConfig>, k_factor: u8) -> Result<()> {
+    ctx.accounts
+        .config
+        .set(ctx.accounts.authority.key(), k_factor)
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+    #[account(address = config.authority())]
+    pub authority: Signer<'info>,
+}
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{game::*, series::*};
+
+/// Scores the series' current game once it is over. Anyone may send this.
+pub fn update_series(ctx: Context<UpdateSeries>) -> Result<()> {
+    let series = &mut ctx.accounts.series;
+    series.record(
+        ctx.accounts.game.key(),
+        ctx.accounts.game.state(),
+        Clock::get()?.unix_timestamp,
+    )?;
+    ctx.accounts.game.mark_scored();
+    if let Some(winner) = series.winner() {
+        msg!("Player {} wins the series", winner);
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateSeries<'info> {
+    #[account(mut)]
+    pub series: Account<'info, Series>,
+    #[account(mut)]
+    pub game: Account<'info, Game>,
+}
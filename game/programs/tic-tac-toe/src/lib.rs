@@ -1,8 +1,11 @@
 use anchor_lang::prelude::*;
 use instructions::*;
-use state::game::Tile;
+use state::game::{CubeTile, GameConfig, Tile};
 
+pub mod ai;
+pub mod elo;
 pub mod errors;
+pub mod events;
 pub mod instructions;
 pub mod state;
 
@@ -13,11 +16,149 @@ declare_id!("6a9rKhtTGmCtKuxJCetCopbW5A5Z3A6hUsBq5mf1i3D");
 pub mod tic_tac_toe {
     use super::*;
 
-    pub fn setup_game(ctx: Context<SetupGame>, player_two: Pubkey) -> Result<()> {
-        setup_game::setup_game(ctx, player_two)
+    pub fn initialize_config(ctx: Context<InitializeConfig>, k_factor: u8) -> Result<()> {
+        initialize_config::initialize_config(ctx, k_factor)
+    }
+
+    pub fn update_config(ctx: Context<UpdateConfig>, k_factor: u8) -> Result<()> {
+        update_config::update_config(ctx, k_factor)
+    }
+
+    pub fn setup_game(
+        ctx: Context<SetupGame>,
+        player_two: Option<Pubkey>,
+        config: GameConfig,
+    ) -> Result<()> {
+        setup_game::setup_game(ctx, player_two, config)
+    }
+
+    pub fn setup_pda_game(
+        ctx: Context<SetupPdaGame>,
+        player_two: Option<Pubkey>,
+        nonce: u64,
+        config: GameConfig,
+    ) -> Result<()> {
+        setup_pda_game::setup_pda_game(ctx, player_two, nonce, config)
+    }
+
+    pub fn create_series(
+        ctx: Context<CreateSeries>,
+        player_two: Pubkey,
+        best_of: u8,
+        config: GameConfig,
+    ) -> Result<()> {
+        create_series::create_series(ctx, player_two, best_of, config)
+    }
+
+    pub fn start_next_game(ctx: Context<StartNextGame>) -> Result<()> {
+        start_next_game::start_next_game(ctx)
+    }
+
+    pub fn update_series(ctx: Context<UpdateSeries>) -> Result<()> {
+        update_series::update_series(ctx)
+    }
+
+    pub fn claim_series_forfeit(ctx: Context<ClaimSeriesForfeit>) -> Result<()> {
+        claim_series_forfeit::claim_series_forfeit(ctx)
+    }
+
+    pub fn create_tournament(
+        ctx: Context<CreateTournament>,
+        size: u8,
+        config: GameConfig,
+    ) -> Result<()> {
+        create_tournament::create_tournament(ctx, size, config)
+    }
+
+    pub fn join_tournament(ctx: Context<JoinTournament>) -> Result<()> {
+        join_tournament::join_tournament(ctx)
+    }
+
+    pub fn start_match(ctx: Context<StartMatch>, index: u8) -> Result<()> {
+        start_match::start_match(ctx, index)
+    }
+
+    pub fn advance(ctx: Context<Advance>, index: u8) -> Result<()> {
+        advance::advance(ctx, index)
+    }
+
+    pub fn create_queue(ctx: Context<CreateQueue>, id: u64, config: GameConfig) -> Result<()> {
+        create_queue::create_queue(ctx, id, config)
+    }
+
+    pub fn enqueue(ctx: Context<Enqueue>, band: Option<u32>) -> Result<()> {
+        enqueue::enqueue(ctx, band)
+    }
+
+    pub fn dequeue(ctx: Context<Dequeue>) -> Result<()> {
+        dequeue::dequeue(ctx)
+    }
+
+    pub fn pair_players(ctx: Context<PairPlayers>) -> Result<()> {
+        pair_players::pair_players(ctx)
+    }
+
+    pub fn join_game(ctx: Context<JoinGame>) -> Result<()> {
+        join_game::join_game(ctx)
+    }
+
+    pub fn match_wager(ctx: Context<MatchWager>) -> Result<()> {
+        match_wager::match_wager(ctx)
+    }
+
+    pub fn commit_secret(ctx: Context<CommitSecret>, commitment: [u8; 32]) -> Result<()> {
+        commit_secret::commit_secret(ctx, commitment)
+    }
+
+    pub fn reveal_secret(ctx: Context<RevealSecret>, secret: [u8; 32]) -> Result<()> {
+        reveal_secret::reveal_secret(ctx, secret)
     }
 
     pub fn play(ctx: Context<Play>, tile: Tile) -> Result<()> {
         play::play(ctx, tile)
     }
+
+    pub fn play_cube(ctx: Context<Play>, tile: CubeTile) -> Result<()> {
+        play_cube::play_cube(ctx, tile)
+    }
+
+    pub fn resign(ctx: Context<Resign>) -> Result<()> {
+        resign::resign(ctx)
+    }
+
+    pub fn offer_draw(ctx: Context<OfferDraw>) -> Result<()> {
+        offer_draw::offer_draw(ctx)
+    }
+
+    pub fn accept_draw(ctx: Context<AcceptDraw>) -> Result<()> {
+        accept_draw::accept_draw(ctx)
+    }
+
+    pub fn rematch(ctx: Context<Rematch>) -> Result<()> {
+        rematch::rematch(ctx)
+    }
+
+    pub fn request_takeback(ctx: Context<RequestTakeback>) -> Result<()> {
+        request_takeback::request_takeback(ctx)
+    }
+
+    pub fn accept_takeback(ctx: Context<AcceptTakeback>) -> Result<()> {
+        accept_takeback::accept_takeback(ctx)
+    }
+
+    pub fn claim_timeout(ctx: Context<ClaimTimeout>) -> Result<()> {
+        claim_timeout::claim_timeout(ctx)
+    }
+
+    pub fn flag(ctx: Context<Flag>) -> Result<()> {
+        flag::flag(ctx)
+    }
+
+    pub fn close_game(ctx: Context<CloseGame>) -> Result<()> {
+        close_game::close_game(ctx)
+    }
+
+    pub fn migrate_game(ctx: Context<MigrateGame>) -> Result<()> {
+        migrate_game::migrate_game(ctx)
+    }
 }
@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::TicTacToeError;
+
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Used until the config has been created
+pub const DEFAULT_K_FACTOR: u8 = 32;
+
+/// Program wide settings, a single PDA
+#[account]
+pub struct Config {
+    authority: Pubkey, // 32
+    k_factor: u8,      // 1
+}
+
+impl Config {
+    pub const MAXIMUM_SIZE: usize = 32 + 1;
+
+    pub fn set(&mut self, authority: Pubkey, k_factor: u8) -> Result<()> {
+        require_gt!(k_factor, 0, TicTacToeError::InvalidKFactor);
+        self.authority = authority;
+        self.k_factor = k_factor;
+        Ok(())
+    }
+
+    pub fn authority(&self) -> Pubkey {
+        self.authority
+    }
+
+    pub fn k_factor(&self) -> u8 {
+        self.k_factor
+    }
+
+    /// The K-factor stored in `config`, which games may end before an admin
+    /// has created.
+    pub fn k_factor_of(config: &AccountInfo) -> Result<u8> {
+        if config.owner == &crate::ID && !config.data_is_empty() {
+            Ok(Self::try_deserialize(&mut &config.try_borrow_data()?[..])?.k_factor())
+        } else {
+            Ok(DEFAULT_K_FACTOR)
+        }
+    }
+}
@@ -1,104 +1,900 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
 
-use crate::errors::TicTacToeError;
-type Board = [[Option<Sign>; 3]; 3];
+use crate::{ai, errors::TicTacToeError};
+type Board = Vec<Option<Sign>>; // row-major, `board_size * board_size` tiles
+
+pub const GAME_SEED: &[u8] = b"game";
 
 #[account]
 pub struct Game {
-    players: [Pubkey; 2], // (32 * 2)
-    turn: u8,             // 1
-    board: Board,         // 9 * (1 + 1) = 18
-    state: GameState,     // 32 + 1
+    version: u8,                        // 1, layout version, see `Game::migrate`
+    players: [Pubkey; 2],               // (32 * 2)
+    turn: u8,                           // 1
+    board: Board,                       // 4 + (tiles * (1 + 1))
+    state: GameState,                   // 32 + 1
+    config: GameConfig,                 // 8 + 8 + 1 + 1 + 1 + 1 + 1 + 8 + 8
+    wager_matched: bool,                // 1
+    payer: Pubkey,                      // 32
+    last_move_at: i64,                  // 8
+    rating_changes: [i32; 2],           // 4 * 2
+    draw_offer: Option<Pubkey>,         // 1 + 32
+    moves: Vec<Move>,                   // 4 + (tiles * 4)
+    mint: Option<Pubkey>,               // 1 + 32, wager is in this token if set
+    meta_board: Vec<Option<Sign>>,      // 4 + (9 * (1 + 1)), empty unless ultimate
+    next_sub_board: Option<u8>,         // 1 + 1, ultimate only
+    first_player: u8,                   // 1, index into `players` of who plays X
+    commitments: [Option<[u8; 32]>; 2], // 2 * (1 + 32), random first player only
+    secrets: [Option<[u8; 32]>; 2],     // 2 * (1 + 32), random first player only
+    remaining_time: [i64; 2],           // 8 * 2, seconds left on each player's clock
+    takeback_request: Option<Pubkey>,   // 1 + 32
+    rematch_offer: Option<Pubkey>,      // 1 + 32, only once the game is over
+    round: u8,                          // 1, bumped by every rematch
+    owner: Option<Pubkey>,              // 1 + 32, the series or tournament the game counts for
+    scored: bool,                       // 1, once the owner has counted the result
 }
 
 impl Game {
-    pub const MAXIMUM_SIZE: usize = (32 * 2) + 1 + (9 * (1 + 1)) + (32 + 1);
+    /// Bumped whenever the fields of `Game` change, with a conversion from
+    /// the previous layout added to `migrate`
+    pub const VERSION: u8 = 4;
+    pub const MIN_BOARD_SIZE: u8 = 3;
+    pub const MAX_BOARD_SIZE: u8 = 15;
+
+    /// Space needed by a game with `config`. Every tile is played at most
+    /// once, so the tile count is also the most moves there are.
+    pub fn maximum_size(config: &GameConfig) -> usize {
+        let tiles = config.tiles();
+        1 + (32 * 2)
+            + 1
+            + (4 + (tiles * (1 + 1)))
+            + (32 + 1)
+            + (8 + 8 + 1 + 1 + 1 + 1 + 1 + 8 + 8)
+            + 1
+            + 32
+            + 8
+            + (4 * 2)
+            + (1 + 32)
+            + (4 + (tiles * Move::SIZE))
+            + (1 + 32)
+            + (4 + (9 * (1 + 1)))
+            + (1 + 1)
+            + 1
+            + (2 * (1 + 32))
+            + (2 * (1 + 32))
+            + (8 * 2)
+            + (1 + 32)
+            + (1 + 32)
+            + 1
+            + (1 + 32)
+            + 1
+    }
 
-    pub fn start(&mut self, players: [Pubkey; 2]) -> Result<()> {
-        // This next error can't happen, because SetupGame is the only
-        // caller of `start`.
-        require_eq!(self.turn, 0, TicTacToeError::GameAlreadyStarted);
-        self.players = players;
+    pub fn start(
+        &mut self,
+        payer: Pubkey,
+        player_one: Pubkey,
+        player_two: Option<Pubkey>,
+        config: GameConfig,
+        mint: Option<Pubkey>,
+        now: i64,
+    ) -> Result<()> {
+        // Either a freshly allocated account or a finished game that is
+        // being rematched
+        require!(
+            self.turn == 0 || self.is_over(),
+            TicTacToeError::GameAlreadyStarted
+        );
+        self.version = Self::VERSION;
+        config.validate()?;
+        // A rematch starts over from whatever the last round left behind
+        self.state = GameState::Active;
+        self.moves.clear();
+        self.meta_board.clear();
+        self.next_sub_board = None;
+        self.rating_changes = [0, 0];
+        self.draw_offer = None;
+        self.takeback_request = None;
+        self.rematch_offer = None;
+        self.wager_matched = false;
+        self.scored = false;
+        self.first_player = 0;
+        self.commitments = [None; 2];
+        self.secrets = [None; 2];
+        match player_two {
+            Some(player_two) => self.players = [player_one, player_two],
+            None => {
+                // Open challenge, the second seat is filled by `join`
+                self.players = [player_one, Pubkey::default()];
+                self.state = GameState::WaitingForOpponent;
+            }
+        }
         self.turn = 1;
+        if config.mode == GameMode::Ultimate {
+            self.meta_board = vec![None; 9];
+        }
+        if player_two == Some(ai::program_player()) {
+            // The program only knows perfect play on a classic board, and
+            // has no wager or secret of its own
+            require!(
+                config.mode == GameMode::Classic
+                    && config.board_size == 3
+                    && config.win_length == 3
+                    && config.wager == 0
+                    && config.first_player == FirstPlayer::PlayerOne,
+                TicTacToeError::UnsupportedAgainstProgram
+            );
+        }
+        self.board = vec![None; config.tiles()];
+        self.remaining_time = [config.clock; 2];
+        self.config = config;
+        self.mint = mint;
+        self.payer = payer;
+        self.last_move_at = now;
+        Ok(())
+    }
+
+    /// Reads a game stored in any layout this program has used, `data`
+    /// following the account discriminator, and converts it to the latest
+    /// layout.
+    pub fn migrate(data: &[u8]) -> Result<Game> {
+        match data.first() {
+            // The original accounts were allocated without any slack
+            _ if data.len() == OriginalGame::SIZE => {
+                let game = OriginalGame::deserialize(&mut &data[..])
+                    .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+                Ok(game.into())
+            }
+            Some(&Self::VERSION) if Game::deserialize(&mut &data[..]).is_ok() => {
+                err!(TicTacToeError::AlreadyMigrated)
+            }
+            Some(3) if GameV3::deserialize(&mut &data[..]).is_ok() => {
+                let game = GameV3::deserialize(&mut &data[..])
+                    .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+                Ok(Game {
+                    takeback_request: game.takeback_request,
+                    rematch_offer: game.rematch_offer,
+                    round: game.round,
+                    ..game.game.into()
+                })
+            }
+            Some(2) if GameV2::deserialize(&mut &data[..]).is_ok() => {
+                let game = GameV2::deserialize(&mut &data[..])
+                    .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+                Ok(Game {
+                    takeback_request: game.takeback_request,
+                    ..game.game.into()
+                })
+            }
+            Some(1) if GameV1::deserialize(&mut &data[..]).is_ok() => {
+                let game = GameV1::deserialize(&mut &data[..])
+                    .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+                Ok(game.game.into())
+            }
+            // Games from before the version byte start straight with the players
+            _ => {
+                let game = GameV0::deserialize(&mut &data[..])
+                    .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+                Ok(game.into())
+            }
+        }
+    }
+
+    pub fn join(&mut self, player: Pubkey, now: i64) -> Result<()> {
+        require!(
+            self.is_waiting_for_opponent(),
+            TicTacToeError::GameAlreadyStarted
+        );
+        require_keys_neq!(player, self.players[0], TicTacToeError::CannotJoinOwnGame);
+        self.players[1] = player;
+        self.state = GameState::Active;
+        // Joining an open challenge always comes with the matching deposit
+        self.wager_matched = self.config.wager > 0;
+        self.last_move_at = now;
         Ok(())
     }
 
+    /// Offers a rematch of a finished game, or accepts the opponent's offer.
+    /// Accepting starts the next round in the same account, with X going to
+    /// whoever played O, or to a fresh handshake when the first player is
+    /// random. Returns whether the next round started.
+    pub fn rematch(&mut self, player: Pubkey, now: i64) -> Result<bool> {
+        require!(self.is_over(), TicTacToeError::GameNotOver);
+        self.player_index(player)?;
+        // Both deposits went out with the payouts
+        require!(self.config.wager == 0, TicTacToeError::RematchWithWager);
+        // Series and tournaments set up their own next games
+        require!(self.owner.is_none(), TicTacToeError::OwnedGame);
+        match self.rematch_offer {
+            Some(offered_by) if offered_by != player => {
+                let first_player = self.first_player ^ 1;
+                let (players, config) = (self.players, self.config);
+                self.start(
+                    self.payer,
+                    players[0],
+                    Some(players[1]),
+                    config,
+                    self.mint,
+                    now,
+                )?;
+                if config.first_player == FirstPlayer::PlayerOne {
+                    self.first_player = first_player;
+                }
+                self.round += 1;
+                Ok(true)
+            }
+            _ => {
+                self.rematch_offer = Some(player);
+                Ok(false)
+            }
+        }
+    }
+
+    pub fn round(&self) -> u8 {
+        self.round
+    }
+
+    /// Marks the game as one that `owner`, a series or tournament, counts
+    /// the result of
+    pub fn set_owner(&mut self, owner: Pubkey) {
+        self.owner = Some(owner);
+    }
+
+    pub fn owner(&self) -> Option<Pubkey> {
+        self.owner
+    }
+
+    /// Called by the owner once it has counted the result
+    pub fn mark_scored(&mut self) {
+        self.scored = true;
+    }
+
+    /// Whether the result can no longer be missed by an owner that still
+    /// has to count it
+    pub fn is_scored(&self) -> bool {
+        self.owner.is_none() || self.scored
+    }
+
     pub fn is_active(&self) -> bool {
         self.state == GameState::Active
     }
 
+    /// Whether player two is the program itself, which replies to every
+    /// move of player one straight away
+    pub fn is_against_program(&self) -> bool {
+        self.players[1] == ai::program_player()
+    }
+
+    pub fn is_over(&self) -> bool {
+        !self.is_active() && !self.is_waiting_for_opponent()
+    }
+
+    pub fn is_waiting_for_opponent(&self) -> bool {
+        self.state == GameState::WaitingForOpponent
+    }
+
     fn current_player_index(&self) -> usize {
-        ((self.turn - 1) & 1) as usize
+        (((self.turn - 1) ^ self.first_player) & 1) as usize
     }
 
-    pub fn current_player(&self) -> Pubkey {
-        self.players[self.current_player_index()]
+    pub fn current_player(&self) -> Result<Pubkey> {
+        require!(
+            !self.is_waiting_for_opponent(),
+            TicTacToeError::WaitingForOpponent
+        );
+        require!(
+            self.is_first_player_decided(),
+            TicTacToeError::FirstPlayerNotDecided
+        );
+        Ok(self.players[self.current_player_index()])
+    }
+
+    /// Whether it is known who plays X. With a random first player that
+    /// takes both players revealing their secrets.
+    pub fn is_first_player_decided(&self) -> bool {
+        self.config.first_player == FirstPlayer::PlayerOne
+            || self.secrets.iter().all(Option::is_some)
+    }
+
+    /// What a player commits to before revealing `secret`. Hashing in the
+    /// player stops the opponent from simply copying their commitment.
+    pub fn commitment(player: &Pubkey, secret: &[u8; 32]) -> [u8; 32] {
+        hashv(&[secret, player.as_ref()]).to_bytes()
+    }
+
+    pub fn commit_secret(&mut self, player: Pubkey, commitment: [u8; 32], now: i64) -> Result<()> {
+        require!(
+            self.is_active() || self.is_waiting_for_opponent(),
+            TicTacToeError::GameAlreadyOver
+        );
+        require!(
+            self.config.first_player == FirstPlayer::Random,
+            TicTacToeError::NoRandomFirstPlayer
+        );
+        let index = self.player_index(player)?;
+        require!(
+            self.commitments[index].is_none(),
+            TicTacToeError::AlreadyCommitted
+        );
+        self.commitments[index] = Some(commitment);
+        self.last_move_at = now;
+        Ok(())
     }
 
-    pub fn play(&mut self, tile: &Tile) -> Result<()> {
+    /// Reveals the secret behind `player`'s commitment. Once both secrets
+    /// are out, their XOR decides who plays X.
+    pub fn reveal_secret(&mut self, player: Pubkey, secret: [u8; 32], now: i64) -> Result<()> {
         require!(self.is_active(), TicTacToeError::GameAlreadyOver);
-        let (row, col) = (tile.0 as usize, tile.1 as usize);
-        require!(row < 3 && col < 3, TicTacToeError::TileOutOfBounds);
-        msg!(
-            "Player {} plays at ({}, {}), old({:?})",
-            self.current_player_index() + 1,
-            row,
-            col,
-            self.board[row][col]
+        require!(
+            self.config.first_player == FirstPlayer::Random,
+            TicTacToeError::NoRandomFirstPlayer
         );
+        let index = self.player_index(player)?;
         require!(
-            self.board[row][col].is_none(),
-            TicTacToeError::TileAlreadySet
+            self.commitments.iter().all(Option::is_some),
+            TicTacToeError::WaitingForCommitment
+        );
+        require!(
+            self.secrets[index].is_none(),
+            TicTacToeError::AlreadyRevealed
+        );
+        require!(
+            self.commitments[index] == Some(Self::commitment(&player, &secret)),
+            TicTacToeError::SecretMismatch
+        );
+        self.secrets[index] = Some(secret);
+        if let [Some(one), Some(two)] = self.secrets {
+            self.first_player = (one[0] ^ two[0]) & 1;
+        }
+        self.last_move_at = now;
+        Ok(())
+    }
+
+    /// How far a player has got through the commit–reveal handshake
+    fn handshake_steps(&self, index: usize) -> u8 {
+        self.commitments[index].is_some() as u8 + self.secrets[index].is_some() as u8
+    }
+
+    pub fn turn(&self) -> u8 {
+        self.turn
+    }
+
+    pub fn players(&self) -> [Pubkey; 2] {
+        self.players
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.payer
+    }
+
+    pub fn mint(&self) -> Option<Pubkey> {
+        self.mint
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn wager(&self) -> u64 {
+        self.config.wager
+    }
+
+    /// Whether player two has put in their wager, or there is none. A game
+    /// whose wager was never matched never really got going.
+    pub fn is_wager_settled(&self) -> bool {
+        self.config.wager == 0 || self.wager_matched
+    }
+
+    pub fn match_wager(&mut self, now: i64) -> Result<()> {
+        require!(self.is_active(), TicTacToeError::GameAlreadyOver);
+        require!(self.config.wager > 0, TicTacToeError::NoWager);
+        require!(!self.wager_matched, TicTacToeError::WagerAlreadyMatched);
+        self.wager_matched = true;
+        // Player one can't move before the wager is matched, so their
+        // clock only starts now.
+        self.last_move_at = now;
+        Ok(())
+    }
+
+    /// Lamports, or tokens for a game with a mint, owed to each player once
+    /// the game is over. A wager that player two never matched is simply
+    /// refunded to player one.
+    pub fn payouts(&self) -> [u64; 2] {
+        if self.is_active() {
+            return [0, 0];
+        }
+        let wager = self.config.wager;
+        if !self.wager_matched {
+            return [wager, 0];
+        }
+        match self.state.winner() {
+            Some(winner) if winner == self.players[0] => [2 * wager, 0],
+            Some(_) => [0, 2 * wager],
+            None => [wager, wager],
+        }
+    }
+
+    pub fn record_rating_changes(&mut self, rating_changes: [i32; 2]) {
+        self.rating_changes = rating_changes;
+    }
+
+    fn player_index(&self, player: Pubkey) -> Result<usize> {
+        Ok(self
+            .players
+            .iter()
+            .position(|p| *p == player)
+            .ok_or(TicTacToeError::NotAPlayer)?)
+    }
+
+    pub fn resign(&mut self, player: Pubkey) -> Result<()> {
+        require!(self.is_active(), TicTacToeError::GameAlreadyOver);
+        let index = self.player_index(player)?;
+        self.state = GameState::Resigned {
+            winner: self.players[1 - index],
+        };
+        self.takeback_request = None;
+        Ok(())
+    }
+
+    /// Records a draw offer from `player`. The offer stands until the next
+    /// move is played.
+    pub fn offer_draw(&mut self, player: Pubkey) -> Result<()> {
+        require!(self.is_active(), TicTacToeError::GameAlreadyOver);
+        self.player_index(player)?;
+        self.draw_offer = Some(player);
+        Ok(())
+    }
+
+    pub fn accept_draw(&mut self, player: Pubkey) -> Result<()> {
+        require!(self.is_active(), TicTacToeError::GameAlreadyOver);
+        self.player_index(player)?;
+        let offered_by = self.draw_offer.ok_or(TicTacToeError::NoDrawOffer)?;
+        require_keys_neq!(offered_by, player, TicTacToeError::CannotAcceptOwnDrawOffer);
+        self.draw_offer = None;
+        self.takeback_request = None;
+        self.state = GameState::Tie;
+        Ok(())
+    }
+
+    /// Asks the opponent to take back `player`'s last move. The request
+    /// stands until the next move is played.
+    pub fn request_takeback(&mut self, player: Pubkey) -> Result<()> {
+        let index = self.player_index(player)?;
+        self.require_can_take_back()?;
+        require!(
+            self.moves
+                .last()
+                .is_some_and(|m| m.player as usize == index),
+            TicTacToeError::NothingToTakeBack
+        );
+        self.takeback_request = Some(player);
+        Ok(())
+    }
+
+    /// Takes back the last move for the player who asked to. A move that
+    /// ended the game reopens it, and it is up to the caller to take the
+    /// result back out of the players' profiles.
+    pub fn accept_takeback(&mut self, player: Pubkey, now: i64) -> Result<()> {
+        self.player_index(player)?;
+        let requested_by = self
+            .takeback_request
+            .ok_or(TicTacToeError::NoTakebackRequest)?;
+        require_keys_neq!(
+            requested_by,
+            player,
+            TicTacToeError::CannotAcceptOwnTakeback
+        );
+        // The game may have ended some other way since the request
+        self.require_can_take_back()?;
+        self.takeback_request = None;
+        let last_move = self.moves.pop().ok_or(TicTacToeError::NothingToTakeBack)?;
+
+        let Tile(row, col) = last_move.tile;
+        let (row, col, layer) = (row as usize, col as usize, last_move.layer as usize);
+        let size = self.config.board_size as usize;
+        match self.config.mode {
+            GameMode::Classic => self.board[row * size + col] = None,
+            GameMode::Ultimate => {
+                self.board[row * 9 + col] = None;
+                // Nothing is played in a decided sub-board, so if it is
+                // decided now it was by the move taken back
+                self.meta_board[row] = None;
+                let previous_cell = self.moves.last().map(|m| m.tile.1);
+                self.next_sub_board =
+                    previous_cell.filter(|&cell| !self.is_sub_board_decided(cell as usize));
+            }
+            GameMode::Cube => self.board[(layer * 4 + row) * 4 + col] = None,
+        }
+
+        // A move that ended the game never handed the turn over
+        if self.is_active() {
+            self.turn -= 1;
+        } else {
+            self.state = GameState::Active;
+            self.rating_changes = [0, 0];
+            // An offer made over the finished game doesn't carry over
+            self.rematch_offer = None;
+        }
+        self.draw_offer = None;
+        self.last_move_at = now;
+        Ok(())
+    }
+
+    /// Checks that the last move can still be taken back, which reopens a
+    /// finished game
+    fn require_can_take_back(&self) -> Result<()> {
+        if self.is_active() {
+            return Ok(());
+        }
+        require!(
+            self.is_ended_by_last_move(),
+            TicTacToeError::GameAlreadyOver
+        );
+        // The pot has already been paid out
+        require!(self.config.wager == 0, TicTacToeError::TakebackAfterPayout);
+        // A series or tournament counts the result as it stands
+        require!(self.owner.is_none(), TicTacToeError::OwnedGame);
+        Ok(())
+    }
+
+    /// Whether the game was ended by a line or a full board rather than by
+    /// a resignation, timeout or agreed draw
+    fn is_ended_by_last_move(&self) -> bool {
+        match self.state {
+            GameState::Won { .. } => true,
+            GameState::Tie => self.is_board_full(),
+            _ => false,
+        }
+    }
+
+    pub fn rating_changes(&self) -> [i32; 2] {
+        self.rating_changes
+    }
+
+    /// Lets the player who is waiting on their opponent claim the win once
+    /// the opponent has used up the per-move timeout.
+    pub fn claim_timeout(&mut self, player: Pubkey, now: i64) -> Result<()> {
+        require!(self.is_active(), TicTacToeError::GameAlreadyOver);
+        require!(self.is_wager_settled(), TicTacToeError::WagerNotMatched);
+        require!(self.config.move_timeout > 0, TicTacToeError::NoMoveTimeout);
+        let claimant = if self.is_first_player_decided() {
+            1 - self.current_player_index()
+        } else {
+            // Stalling the commit–reveal handshake forfeits like a move would
+            let index = self.player_index(player)?;
+            require_gt!(
+                self.handshake_steps(index),
+                self.handshake_steps(1 - index),
+                TicTacToeError::NotPlayersTurn
+            );
+            index
+        };
+        require_keys_eq!(
+            self.players[claimant],
+            player,
+            TicTacToeError::NotPlayersTurn
+        );
+        require_gte!(
+            now,
+            self.last_move_at + self.config.move_timeout,
+            TicTacToeError::TimeoutNotReached
+        );
+        self.state = GameState::TimedOut { winner: player };
+        self.takeback_request = None;
+        Ok(())
+    }
+
+    /// Ends the game as a loss on time for the player to move once their
+    /// clock has run out. Anyone may check this.
+    pub fn flag(&mut self, now: i64) -> Result<()> {
+        require!(self.is_active(), TicTacToeError::GameAlreadyOver);
+        require!(self.is_wager_settled(), TicTacToeError::WagerNotMatched);
+        require!(self.config.clock > 0, TicTacToeError::NoClock);
+        // Errors out while the first player is still being decided
+        self.current_player()?;
+        let index = self.current_player_index();
+        require_gte!(
+            now - self.last_move_at,
+            self.remaining_time[index],
+            TicTacToeError::TimeoutNotReached
+        );
+        self.remaining_time[index] = 0;
+        self.state = GameState::TimedOut {
+            winner: self.players[1 - index],
+        };
+        self.takeback_request = None;
+        Ok(())
+    }
+
+    pub fn remaining_time(&self) -> [i64; 2] {
+        self.remaining_time
+    }
+
+    pub fn play(&mut self, tile: &Tile, now: i64) -> Result<()> {
+        self.require_can_move()?;
+        self.run_clock(now)?;
+        let is_winning_move = match self.config.mode {
+            GameMode::Classic => self.play_classic(tile)?,
+            GameMode::Ultimate => self.play_ultimate(tile)?,
+            GameMode::Cube => return err!(TicTacToeError::WrongGameMode),
+        };
+        self.finish_move(tile.clone(), 0, is_winning_move, now);
+        if self.is_active() && self.is_against_program() {
+            self.play_program_move(now)?;
+        }
+        Ok(())
+    }
+
+    fn play_program_move(&mut self, now: i64) -> Result<()> {
+        let sign = self.current_sign().unwrap_or(Sign::O);
+        let misere = self.config.rules == Rules::Misere;
+        let index =
+            ai::best_move(&self.board, sign, misere).ok_or(TicTacToeError::GameAlreadyOver)?;
+        let tile = Tile(index as u8 / 3, index as u8 % 3);
+        self.run_clock(now)?;
+        let is_winning_move = self.play_classic(&tile)?;
+        self.finish_move(tile, 0, is_winning_move, now);
+        Ok(())
+    }
+
+    pub fn play_cube(&mut self, tile: &CubeTile, now: i64) -> Result<()> {
+        self.require_can_move()?;
+        self.run_clock(now)?;
+        require!(
+            self.config.mode == GameMode::Cube,
+            TicTacToeError::WrongGameMode
+        );
+        let CubeTile(layer, row, col) = *tile;
+        require!(
+            layer < 4 && row < 4 && col < 4,
+            TicTacToeError::TileOutOfBounds
         );
-        self.board[row][col] = Some(Sign::from(self.current_player_index()));
+        let index = (layer as usize * 4 + row as usize) * 4 + col as usize;
+        require!(self.board[index].is_none(), TicTacToeError::TileAlreadySet);
+        self.board[index] = self.current_sign();
+        let is_winning_move = is_winning_cube_line(&self.board, [layer, row, col]);
+        self.finish_move(Tile(row, col), layer, is_winning_move, now);
+        Ok(())
+    }
 
-        self.update_state();
+    fn require_can_move(&self) -> Result<()> {
+        require!(
+            !self.is_waiting_for_opponent(),
+            TicTacToeError::WaitingForOpponent
+        );
+        require!(self.is_active(), TicTacToeError::GameAlreadyOver);
+        require!(self.is_wager_settled(), TicTacToeError::WagerNotMatched);
+        require!(
+            self.is_first_player_decided(),
+            TicTacToeError::FirstPlayerNotDecided
+        );
+        Ok(())
+    }
+
+    /// Charges the time since the last move to the player moving now and
+    /// adds the increment. A player whose clock has run out can only be
+    /// flagged.
+    fn run_clock(&mut self, now: i64) -> Result<()> {
+        if self.config.clock == 0 {
+            return Ok(());
+        }
+        let index = self.current_player_index();
+        let remaining_time = self.remaining_time[index] - (now - self.last_move_at);
+        require_gt!(remaining_time, 0, TicTacToeError::OutOfTime);
+        self.remaining_time[index] = remaining_time + self.config.increment;
+        Ok(())
+    }
+
+    /// Records a move that has been placed on the board and hands the turn
+    /// over, unless the move ended the game.
+    fn finish_move(&mut self, tile: Tile, layer: u8, is_winning_move: bool, now: i64) {
+        self.moves.push(Move {
+            tile,
+            player: self.current_player_index() as u8,
+            layer,
+        });
+        self.last_move_at = now;
+        self.draw_offer = None;
+        self.takeback_request = None;
+
+        self.update_state(is_winning_move);
 
         if GameState::Active == self.state {
             self.turn += 1;
         }
+    }
 
-        Ok(())
+    pub fn last_move(&self) -> Option<&Move> {
+        self.moves.last()
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
     }
 
-    fn is_winning_trio(&self, trio: [(usize, usize); 3]) -> bool {
-        let [first, second, third] = trio;
-        self.board[first.0][first.1].is_some()
-            && self.board[first.0][first.1] == self.board[second.0][second.1]
-            && self.board[first.0][first.1] == self.board[third.0][third.1]
+    /// X always moves first, whichever player that is
+    fn current_sign(&self) -> Option<Sign> {
+        Some(Sign::from(((self.turn - 1) & 1) as usize))
     }
 
-    fn update_state(&mut self) {
-        if self.turn >= 5
-            && (self.is_winning_trio([(0, 0), (0, 1), (0, 2)]) || // row 0
-                self.is_winning_trio([(1, 0), (1, 1), (1, 2)]) || // row 1
-                self.is_winning_trio([(2, 0), (2, 1), (2, 2)]) || // row 2
-                self.is_winning_trio([(0, 0), (1, 0), (2, 0)]) || // column 0
-                self.is_winning_trio([(0, 1), (1, 1), (2, 1)]) || // column 1
-                self.is_winning_trio([(0, 2), (1, 2), (2, 2)]) || // column 2
-                self.is_winning_trio([(0, 0), (1, 1), (2, 2)]) || // diagonal left to right
-                self.is_winning_trio([(0, 2), (1, 1), (2, 0)] /*diagonal right to left*/))
-        {
-            // diagonal right to left
+    /// Plays (row, column) on the single board and returns whether it won
+    /// the game.
+    fn play_classic(&mut self, tile: &Tile) -> Result<bool> {
+        let size = self.config.board_size as usize;
+        let (row, col) = (tile.0 as usize, tile.1 as usize);
+        require!(row < size && col < size, TicTacToeError::TileOutOfBounds);
+        require!(
+            self.board[row * size + col].is_none(),
+            TicTacToeError::TileAlreadySet
+        );
+        self.board[row * size + col] = self.current_sign();
+        Ok(is_winning_line(
+            &self.board,
+            size,
+            row,
+            col,
+            self.config.win_length as usize,
+        ))
+    }
+
+    /// Plays (sub-board, cell) and returns whether it won the meta-board.
+    /// Sub-boards and their cells are both numbered row by row.
+    fn play_ultimate(&mut self, tile: &Tile) -> Result<bool> {
+        let (sub_board, cell) = (tile.0 as usize, tile.1 as usize);
+        require!(sub_board < 9 && cell < 9, TicTacToeError::TileOutOfBounds);
+        if let Some(next_sub_board) = self.next_sub_board {
+            require_eq!(
+                sub_board,
+                next_sub_board as usize,
+                TicTacToeError::WrongSubBoard
+            );
+        }
+        require!(
+            !self.is_sub_board_decided(sub_board),
+            TicTacToeError::SubBoardAlreadyDecided
+        );
+        let sign = self.current_sign();
+        let tiles = &mut self.board[sub_board * 9..(sub_board + 1) * 9];
+        require!(tiles[cell].is_none(), TicTacToeError::TileAlreadySet);
+        tiles[cell] = sign;
+
+        let mut is_winning_move = false;
+        if is_winning_line(tiles, 3, cell / 3, cell % 3, 3) {
+            self.meta_board[sub_board] = sign;
+            is_winning_move = is_winning_line(&self.meta_board, 3, sub_board / 3, sub_board % 3, 3);
+        }
+        // The opponent has to answer in the sub-board matching this cell,
+        // unless that one is already decided and they may play anywhere
+        self.next_sub_board = (!self.is_sub_board_decided(cell)).then_some(cell as u8);
+        Ok(is_winning_move)
+    }
+
+    /// Whether a sub-board has been won or filled up
+    fn is_sub_board_decided(&self, sub_board: usize) -> bool {
+        self.meta_board[sub_board].is_some()
+            || self.board[sub_board * 9..(sub_board + 1) * 9]
+                .iter()
+                .all(Option::is_some)
+    }
+
+    fn is_board_full(&self) -> bool {
+        match self.config.mode {
+            GameMode::Classic | GameMode::Cube => self.turn as usize >= self.config.tiles(),
+            GameMode::Ultimate => (0..9).all(|sub_board| self.is_sub_board_decided(sub_board)),
+        }
+    }
+
+    fn update_state(&mut self, is_winning_move: bool) {
+        if is_winning_move {
+            // Under misère rules completing a line loses
+            let winner = match self.config.rules {
+                Rules::Normal => self.current_player_index(),
+                Rules::Misere => 1 - self.current_player_index(),
+            };
             self.state = GameState::Won {
-                winner: self.current_player(),
+                winner: self.players[winner],
             };
             return;
         }
 
-        // maintain the state as Active if there have been less than 9 turns
-        // and no one has won yet
-        if self.turn >= 9 {
+        // maintain the state as Active until the board is full and no one
+        // has won yet
+        if self.is_board_full() {
             self.state = GameState::Tie;
         }
     }
 }
 
+/// Counts how many tiles in a row, starting next to (`row`, `col`) and
+/// stepping by (`row_step`, `col_step`), hold the same sign as that tile.
+fn count_in_direction(
+    tiles: &[Option<Sign>],
+    size: usize,
+    (row, col): (usize, usize),
+    (row_step, col_step): (isize, isize),
+) -> usize {
+    let size = size as isize;
+    let sign = tiles[row * size as usize + col];
+    let (mut r, mut c) = (row as isize + row_step, col as isize + col_step);
+    let mut count = 0;
+    while r >= 0 && r < size && c >= 0 && c < size && tiles[(r * size + c) as usize] == sign {
+        count += 1;
+        r += row_step;
+        c += col_step;
+    }
+    count
+}
+
+/// Whether the tile just played at (`row`, `col`) of a `size` by `size`
+/// board completes a line of `win_length` signs. Only lines through that
+/// tile can be new.
+fn is_winning_line(
+    tiles: &[Option<Sign>],
+    size: usize,
+    row: usize,
+    col: usize,
+    win_length: usize,
+) -> bool {
+    // row, column, diagonal and anti-diagonal
+    [(0, 1), (1, 0), (1, 1), (1, -1)]
+        .into_iter()
+        .any(|(row_step, col_step)| {
+            1 + count_in_direction(tiles, size, (row, col), (row_step, col_step))
+                + count_in_direction(tiles, size, (row, col), (-row_step, -col_step))
+                >= win_length
+        })
+}
+
+/// Whether the tile just played at `[layer, row, col]` of a 4x4x4 cube
+/// completes one of the cube's 76 lines of four, which run along the 13
+/// directions through a tile.
+fn is_winning_cube_line(tiles: &[Option<Sign>], tile: [u8; 3]) -> bool {
+    let index = |[layer, row, col]: [isize; 3]| ((layer * 4 + row) * 4 + col) as usize;
+    let start = tile.map(|coordinate| coordinate as isize);
+    let sign = tiles[index(start)];
+    let count_in_direction = |step: [isize; 3]| {
+        let mut position = start;
+        let mut count = 0;
+        loop {
+            for axis in 0..3 {
+                position[axis] += step[axis];
+            }
+            if position.iter().any(|&p| !(0..4).contains(&p)) || tiles[index(position)] != sign {
+                return count;
+            }
+            count += 1;
+        }
+    };
+
+    // One of each pair of opposite directions out of the 26 neighbours
+    (0..27)
+        .map(|i| [i / 9 - 1, i / 3 % 3 - 1, i % 3 - 1])
+        .filter(|step| *step > [0, 0, 0])
+        .any(|step| 1 + count_in_direction(step) + count_in_direction(step.map(|s| -s)) >= 4)
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
 pub enum GameState {
     Active,
     Tie,
     Won { winner: Pubkey },
+    Resigned { winner: Pubkey },
+    TimedOut { winner: Pubkey },
+    WaitingForOpponent,
+}
+
+impl GameState {
+    pub fn winner(&self) -> Option<Pubkey> {
+        match self {
+            GameState::Won { winner }
+            | GameState::Resigned { winner }
+            | GameState::TimedOut { winner } => Some(*winner),
+            GameState::Active | GameState::Tie | GameState::WaitingForOpponent => None,
+        }
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Debug, Copy, Clone, PartialEq, Eq)]
@@ -117,5 +913,920 @@ impl From<usize> for Sign {
     }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct Tile(u8, u8); // row, column
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct CubeTile(u8, u8, u8); // layer, row, column
+
+/// One entry of a game's move history
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Move {
+    pub tile: Tile, // 2
+    pub player: u8, // 1, index into `Game::players`
+    pub layer: u8,  // 1, always 0 outside of cube games
+}
+
+impl Move {
+    pub const SIZE: usize = 2 + 1 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Classic,
+    /// Nine 3x3 sub-boards, won sub-boards are the marks on a 3x3 meta-board
+    Ultimate,
+    /// A 4x4x4 cube played with `CubeTile`s, any line of four wins
+    Cube,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rules {
+    #[default]
+    Normal,
+    /// Whoever completes a line loses
+    Misere,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FirstPlayer {
+    #[default]
+    PlayerOne,
+    /// Decided by a commit–reveal handshake before the first move
+    Random,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct GameConfig {
+    pub wager: u64,                // lamports each player puts in the pot
+    pub move_timeout: i64,         // seconds per move, 0 for no timeout
+    pub board_size: u8,            // tiles per side, 3 for classic tic-tac-toe
+    pub win_length: u8,            // signs in a row needed to win
+    pub mode: GameMode,            // 1
+    pub rules: Rules,              // 1
+    pub first_player: FirstPlayer, // 1
+    pub clock: i64,                // seconds on each player's clock, 0 for no clock
+    pub increment: i64,            // seconds added to a player's clock after each move
+}
+
+impl GameConfig {
+    /// Checks everything `Game::start` needs of a config, so that series,
+    /// tournaments and queues can refuse a bad one before anyone joins
+    pub fn validate(&self) -> Result<()> {
+        require_gte!(self.move_timeout, 0, TicTacToeError::InvalidMoveTimeout);
+        require!(
+            self.clock >= 0 && self.increment >= 0,
+            TicTacToeError::InvalidClock
+        );
+        require!(
+            (Game::MIN_BOARD_SIZE..=Game::MAX_BOARD_SIZE).contains(&self.board_size),
+            TicTacToeError::InvalidBoardSize
+        );
+        require!(
+            (Game::MIN_BOARD_SIZE..=self.board_size).contains(&self.win_length),
+            TicTacToeError::InvalidWinLength
+        );
+        // Whoever reveals second already knows who plays X, and refusing to
+        // reveal has to forfeit on time rather than lock the game
+        require!(
+            self.first_player != FirstPlayer::Random || self.move_timeout > 0,
+            TicTacToeError::NoMoveTimeout
+        );
+        match self.mode {
+            GameMode::Classic => {}
+            // Sub-boards and the meta-board are all classic 3x3 boards
+            GameMode::Ultimate => require!(
+                self.board_size == 3 && self.win_length == 3,
+                TicTacToeError::InvalidBoardSize
+            ),
+            GameMode::Cube => require!(
+                self.board_size == 4 && self.win_length == 4,
+                TicTacToeError::InvalidBoardSize
+            ),
+        }
+        Ok(())
+    }
+
+    pub fn tiles(&self) -> usize {
+        match self.mode {
+            GameMode::Classic => self.board_size as usize * self.board_size as usize,
+            GameMode::Ultimate => 9 * 9,
+            GameMode::Cube => 4 * 4 * 4,
+        }
+    }
+}
+
+/// The layout of `Game` deployed before any of the later features, only
+/// ever a 3x3 game between two players
+#[derive(AnchorSerialize, AnchorDeserialize)]
+struct OriginalGame {
+    players: [Pubkey; 2],
+    turn: u8,
+    board: [[Option<Sign>; 3]; 3],
+    state: GameState,
+}
+
+impl OriginalGame {
+    const SIZE: usize = (32 * 2) + 1 + (9 * (1 + 1)) + (32 + 1);
+}
+
+impl From<OriginalGame> for Game {
+    fn from(game: OriginalGame) -> Self {
+        GameV0 {
+            players: game.players,
+            turn: game.turn,
+            board: game.board.concat(),
+            state: game.state,
+            config: GameConfig {
+                board_size: 3,
+                win_length: 3,
+                ..GameConfig::default()
+            },
+            wager_matched: false,
+            // Player one paid for every original game
+            payer: game.players[0],
+            last_move_at: 0,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            // The moves that got the board there weren't recorded
+            moves: vec![],
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+        }
+        .into()
+    }
+}
+
+/// The layout of `Game` before it had a version
+#[derive(AnchorSerialize, AnchorDeserialize)]
+struct GameV0 {
+    players: [Pubkey; 2],
+    turn: u8,
+    board: Board,
+    state: GameState,
+    config: GameConfig,
+    wager_matched: bool,
+    payer: Pubkey,
+    last_move_at: i64,
+    rating_changes: [i32; 2],
+    draw_offer: Option<Pubkey>,
+    moves: Vec<Move>,
+    mint: Option<Pubkey>,
+    meta_board: Vec<Option<Sign>>,
+    next_sub_board: Option<u8>,
+    first_player: u8,
+    commitments: [Option<[u8; 32]>; 2],
+    secrets: [Option<[u8; 32]>; 2],
+    remaining_time: [i64; 2],
+}
+
+/// Version 1 only put the version byte in front of the version 0 layout
+#[derive(AnchorSerialize, AnchorDeserialize)]
+struct GameV1 {
+    version: u8,
+    game: GameV0,
+}
+
+/// Version 2 added takebacks
+#[derive(AnchorSerialize, AnchorDeserialize)]
+struct GameV2 {
+    version: u8,
+    game: GameV0,
+    takeback_request: Option<Pubkey>,
+}
+
+/// Version 3 added rematches
+#[derive(AnchorSerialize, AnchorDeserialize)]
+struct GameV3 {
+    version: u8,
+    game: GameV0,
+    takeback_request: Option<Pubkey>,
+    rematch_offer: Option<Pubkey>,
+    round: u8,
+}
+
+impl From<GameV0> for Game {
+    fn from(game: GameV0) -> Self {
+        Game {
+            version: Game::VERSION,
+            players: game.players,
+            turn: game.turn,
+            board: game.board,
+            state: game.state,
+            config: game.config,
+            wager_matched: game.wager_matched,
+            payer: game.payer,
+            last_move_at: game.last_move_at,
+            rating_changes: game.rating_changes,
+            draw_offer: game.draw_offer,
+            moves: game.moves,
+            mint: game.mint,
+            meta_board: game.meta_board,
+            next_sub_board: game.next_sub_board,
+            first_player: game.first_player,
+            commitments: game.commitments,
+            secrets: game.secrets,
+            remaining_time: game.remaining_time,
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_game(board_size: u8, win_length: u8) -> Game {
+        new_game_with_mode(board_size, win_length, GameMode::Classic)
+    }
+
+    fn new_game_with_mode(board_size: u8, win_length: u8, mode: GameMode) -> Game {
+        let mut game = Game {
+            version: 0,
+            players: [Pubkey::default(); 2],
+            turn: 0,
+            board: vec![],
+            state: GameState::Active,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: Pubkey::default(),
+            last_move_at: 0,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            moves: vec![],
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
+        };
+        let config = GameConfig {
+            board_size,
+            win_length,
+            mode,
+            ..GameConfig::default()
+        };
+        let players = [Pubkey::new_unique(), Pubkey::new_unique()];
+        game.start(players[0], players[0], Some(players[1]), config, None, 0)
+            .unwrap();
+        game
+    }
+
+    fn play_all(game: &mut Game, tiles: &[(u8, u8)]) {
+        for &(row, col) in tiles {
+            game.play(&Tile(row, col), 0).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_config() {
+        for (board_size, win_length) in [(2, 2), (16, 5), (4, 5), (5, 2)] {
+            let mut game = new_game(3, 3);
+            game.turn = 0;
+            let config = GameConfig {
+                board_size,
+                win_length,
+                ..GameConfig::default()
+            };
+            assert!(game
+                .start(Pubkey::default(), Pubkey::default(), None, config, None, 0)
+                .is_err());
+        }
+
+        let mut game = new_game(3, 3);
+        game.turn = 0;
+        let config = GameConfig {
+            first_player: FirstPlayer::Random,
+            ..GameConfig::default()
+        };
+        assert!(game
+            .start(Pubkey::default(), Pubkey::default(), None, config, None, 0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_win_in_every_direction() {
+        let lines: [&[(u8, u8)]; 4] = [
+            &[(2, 0), (0, 0), (2, 1), (0, 1), (2, 2), (0, 4), (2, 3)], // row
+            &[(0, 1), (0, 0), (1, 1), (0, 2), (2, 1), (0, 4), (3, 1)], // column
+            &[(0, 0), (0, 1), (1, 1), (0, 2), (2, 2), (0, 4), (3, 3)], // diagonal
+            &[(0, 4), (0, 0), (1, 3), (0, 1), (2, 2), (0, 2), (3, 1)], // anti-diagonal
+        ];
+        for tiles in lines {
+            let mut game = new_game(5, 4);
+            play_all(&mut game, tiles);
+            assert!(
+                game.state
+                    == GameState::Won {
+                        winner: game.players[0]
+                    }
+            );
+        }
+    }
+
+    #[test]
+    fn test_line_shorter_than_win_length() {
+        let mut game = new_game(5, 4);
+        play_all(&mut game, &[(2, 0), (0, 0), (2, 1), (0, 1), (2, 2)]);
+        assert!(game.is_active());
+    }
+
+    #[test]
+    fn test_tie_on_full_board() {
+        let mut game = new_game(4, 4);
+        // Rows alternate between X X O O and O O X X, so no row, column or
+        // diagonal of four forms
+        play_all(
+            &mut game,
+            &[
+                (0, 0),
+                (0, 2),
+                (0, 1),
+                (0, 3),
+                (1, 2),
+                (1, 0),
+                (1, 3),
+                (1, 1),
+                (2, 0),
+                (2, 2),
+                (2, 1),
+                (2, 3),
+                (3, 2),
+                (3, 0),
+                (3, 3),
+                (3, 1),
+            ],
+        );
+        assert!(game.state == GameState::Tie);
+    }
+
+    #[test]
+    fn test_tile_out_of_bounds() {
+        let mut game = new_game(4, 3);
+        assert!(game.play(&Tile(4, 0), 0).is_err());
+        assert!(game.play(&Tile(3, 3), 0).is_ok());
+    }
+
+    /// Player one takes the middle rows of the top three sub-boards, while
+    /// player two keeps sending them back there.
+    const ULTIMATE_WIN: [(u8, u8); 17] = [
+        (0, 3),
+        (3, 1),
+        (1, 4),
+        (4, 2),
+        (2, 5),
+        (5, 0),
+        (0, 4),
+        (4, 1),
+        (1, 5),
+        (5, 2),
+        (2, 3),
+        (3, 0),
+        (0, 5),
+        (5, 1),
+        (1, 3),
+        (3, 2),
+        (2, 4),
+    ];
+
+    #[test]
+    fn test_ultimate_win_on_meta_board() {
+        let mut game = new_game_with_mode(3, 3, GameMode::Ultimate);
+        play_all(&mut game, &ULTIMATE_WIN[..16]);
+        assert!(game.is_active());
+        assert_eq!(
+            game.meta_board,
+            vec![
+                Some(Sign::X),
+                Some(Sign::X),
+                None,
+                Some(Sign::O),
+                None,
+                Some(Sign::O),
+                None,
+                None,
+                None
+            ]
+        );
+        play_all(&mut game, &ULTIMATE_WIN[16..]);
+        assert!(
+            game.state
+                == GameState::Won {
+                    winner: game.players[0]
+                }
+        );
+    }
+
+    #[test]
+    fn test_ultimate_sub_board_rules() {
+        let mut game = new_game_with_mode(3, 3, GameMode::Ultimate);
+        game.play(&Tile(4, 0), 0).unwrap();
+        // Cell 0 sends the opponent to sub-board 0
+        assert_eq!(game.next_sub_board, Some(0));
+        assert!(game.play(&Tile(1, 0), 0).is_err());
+        assert!(game.play(&Tile(0, 9), 0).is_err());
+        game.play(&Tile(0, 4), 0).unwrap();
+
+        // A decided sub-board can't be played, and being sent to one lets
+        // the player go anywhere
+        let mut game = new_game_with_mode(3, 3, GameMode::Ultimate);
+        play_all(&mut game, &ULTIMATE_WIN[..13]);
+        assert_eq!(game.meta_board[0], Some(Sign::X));
+        play_all(&mut game, &[(5, 1), (1, 0)]);
+        assert_eq!(game.next_sub_board, None);
+        assert!(game.play(&Tile(0, 0), 0).is_err());
+        game.play(&Tile(8, 8), 0).unwrap();
+    }
+
+    #[test]
+    fn test_misere_line_loses() {
+        let mut game = new_game(3, 3);
+        game.config.rules = Rules::Misere;
+        play_all(&mut game, &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)]);
+        assert!(
+            game.state
+                == GameState::Won {
+                    winner: game.players[1]
+                }
+        );
+    }
+
+    fn commit_and_reveal(game: &mut Game, secrets: [[u8; 32]; 2]) {
+        let players = game.players;
+        for (player, secret) in players.iter().zip(&secrets) {
+            game.commit_secret(*player, Game::commitment(player, secret), 0)
+                .unwrap();
+        }
+        for (player, secret) in players.iter().zip(secrets) {
+            game.reveal_secret(*player, secret, 0).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_random_first_player() {
+        for (secrets, first_player) in [([[1; 32], [2; 32]], 1), ([[1; 32], [3; 32]], 0)] {
+            let mut game = new_game(3, 3);
+            game.config.first_player = FirstPlayer::Random;
+            assert!(game.current_player().is_err());
+            assert!(game.play(&Tile(0, 0), 0).is_err());
+
+            commit_and_reveal(&mut game, secrets);
+            assert_eq!(game.current_player().unwrap(), game.players[first_player]);
+            game.play(&Tile(0, 0), 0).unwrap();
+            assert_eq!(game.last_move().unwrap().player as usize, first_player);
+            assert!(game.board[0] == Some(Sign::X));
+        }
+    }
+
+    #[test]
+    fn test_reveal_must_match_commitment() {
+        let mut game = new_game(3, 3);
+        game.config.first_player = FirstPlayer::Random;
+        let [one, two] = game.players;
+        game.commit_secret(one, Game::commitment(&one, &[1; 32]), 0)
+            .unwrap();
+        assert!(game.reveal_secret(one, [1; 32], 0).is_err());
+
+        // A copied commitment can't be opened by the copier
+        game.commit_secret(two, Game::commitment(&one, &[1; 32]), 0)
+            .unwrap();
+        assert!(game.reveal_secret(two, [1; 32], 0).is_err());
+        assert!(game.reveal_secret(one, [2; 32], 0).is_err());
+        game.reveal_secret(one, [1; 32], 0).unwrap();
+        assert!(game.reveal_secret(one, [1; 32], 0).is_err());
+    }
+
+    #[test]
+    fn test_not_revealing_forfeits() {
+        let mut game = new_game(3, 3);
+        game.config.first_player = FirstPlayer::Random;
+        game.config.move_timeout = 10;
+        let [one, two] = game.players;
+        game.commit_secret(one, Game::commitment(&one, &[1; 32]), 0)
+            .unwrap();
+        game.commit_secret(two, Game::commitment(&two, &[2; 32]), 0)
+            .unwrap();
+        game.reveal_secret(two, [2; 32], 0).unwrap();
+
+        assert!(game.claim_timeout(one, 10).is_err());
+        assert!(game.claim_timeout(two, 9).is_err());
+        game.claim_timeout(two, 10).unwrap();
+        assert!(game.state == GameState::TimedOut { winner: two });
+    }
+
+    #[test]
+    fn test_clock() {
+        let mut game = new_game(3, 3);
+        game.config.clock = 60;
+        game.config.increment = 2;
+        game.remaining_time = [60; 2];
+
+        game.play(&Tile(0, 0), 10).unwrap();
+        game.play(&Tile(1, 1), 15).unwrap();
+        assert_eq!(game.remaining_time(), [52, 57]);
+
+        // Player one has until 67 to move
+        assert!(game.flag(66).is_err());
+        assert!(game.play(&Tile(2, 2), 67).is_err());
+        game.flag(67).unwrap();
+        assert!(
+            game.state
+                == GameState::TimedOut {
+                    winner: game.players[1]
+                }
+        );
+    }
+
+    #[test]
+    fn test_flag_needs_clock() {
+        let mut game = new_game(3, 3);
+        assert!(game.flag(1_000_000).is_err());
+        game.play(&Tile(0, 0), 1_000_000).unwrap();
+    }
+
+    #[test]
+    fn test_flag_against_yourself() {
+        let mut game = new_game(3, 3);
+        game.players[1] = game.players[0];
+        game.config.clock = 60;
+        game.remaining_time = [60; 2];
+
+        // O is on move, and only O's clock has run out
+        game.play(&Tile(0, 0), 50).unwrap();
+        assert!(game.flag(109).is_err());
+        game.flag(110).unwrap();
+        assert_eq!(game.remaining_time(), [10, 0]);
+    }
+
+    fn as_v0(game: &Game) -> GameV0 {
+        GameV0 {
+            players: game.players,
+            turn: game.turn,
+            board: game.board.clone(),
+            state: game.state.clone(),
+            config: game.config,
+            wager_matched: game.wager_matched,
+            payer: game.payer,
+            last_move_at: game.last_move_at,
+            rating_changes: game.rating_changes,
+            draw_offer: game.draw_offer,
+            moves: game.moves.clone(),
+            mint: game.mint,
+            meta_board: game.meta_board.clone(),
+            next_sub_board: game.next_sub_board,
+            first_player: game.first_player,
+            commitments: game.commitments,
+            secrets: game.secrets,
+            remaining_time: game.remaining_time,
+        }
+    }
+
+    #[test]
+    fn test_migrate_from_old_versions() {
+        let mut game = new_game(3, 3);
+        play_all(&mut game, &[(0, 0), (1, 1)]);
+        let mut expected = vec![];
+        game.serialize(&mut expected).unwrap();
+
+        let mut v0 = vec![];
+        as_v0(&game).serialize(&mut v0).unwrap();
+        let mut v1 = vec![];
+        GameV1 {
+            version: 1,
+            game: as_v0(&game),
+        }
+        .serialize(&mut v1)
+        .unwrap();
+        let mut v2 = vec![];
+        GameV2 {
+            version: 2,
+            game: as_v0(&game),
+            takeback_request: game.takeback_request,
+        }
+        .serialize(&mut v2)
+        .unwrap();
+        let mut v3 = vec![];
+        GameV3 {
+            version: 3,
+            game: as_v0(&game),
+            takeback_request: game.takeback_request,
+            rematch_offer: game.rematch_offer,
+            round: game.round,
+        }
+        .serialize(&mut v3)
+        .unwrap();
+        for mut data in [v0, v1, v2, v3] {
+            // Accounts are allocated for the longest game, so there is slack
+            data.resize(Game::maximum_size(&game.config), 0);
+            let migrated = Game::migrate(&data).unwrap();
+            let mut actual = vec![];
+            migrated.serialize(&mut actual).unwrap();
+            assert_eq!(actual, expected);
+        }
+
+        // Migrating again has nothing left to do
+        expected.resize(Game::maximum_size(&game.config), 0);
+        assert!(Game::migrate(&expected).is_err());
+    }
+
+    #[test]
+    fn test_migrate_original_game() {
+        let (x, o) = (Some(Sign::X), Some(Sign::O));
+        let players = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut data = vec![];
+        OriginalGame {
+            players,
+            turn: 4,
+            board: [[x, None, None], [None, o, None], [None, None, x]],
+            state: GameState::Active,
+        }
+        .serialize(&mut data)
+        .unwrap();
+        data.resize(OriginalGame::SIZE, 0);
+
+        let mut game = Game::migrate(&data).unwrap();
+        assert_eq!(game.version, Game::VERSION);
+        assert_eq!(game.players(), players);
+        assert_eq!(game.payer(), players[0]);
+        assert_eq!(game.board, [x, None, None, None, o, None, None, None, x]);
+        assert_eq!(game.config.tiles(), 9);
+        // The game carries on where it was left
+        play_all(&mut game, &[(0, 1), (1, 0), (1, 2), (2, 0)]);
+        assert!(game.state == GameState::Won { winner: players[0] });
+    }
+
+    #[test]
+    fn test_owned_game() {
+        let mut game = new_game(3, 3);
+        assert!(game.is_scored());
+        game.set_owner(Pubkey::new_unique());
+        play_all(&mut game, &[(0, 0), (1, 0), (0, 1), (1, 1)]);
+        // Moves can be taken back, but not the one that decided the game
+        game.request_takeback(game.players[1]).unwrap();
+        play_all(&mut game, &[(0, 2)]);
+        assert!(game.request_takeback(game.players[0]).is_err());
+        game.takeback_request = Some(game.players[0]);
+        assert!(game.accept_takeback(game.players[1], 0).is_err());
+        assert!(!game.is_scored());
+        game.mark_scored();
+        assert!(game.is_scored());
+        assert!(game.rematch(game.players[1], 0).is_err());
+    }
+
+    #[test]
+    fn test_takeback() {
+        let mut game = new_game(3, 3);
+        let [one, two] = game.players;
+        play_all(&mut game, &[(0, 0), (1, 1)]);
+        // Only the player who made the last move can ask for it back
+        assert!(game.request_takeback(one).is_err());
+        game.request_takeback(two).unwrap();
+        assert!(game.accept_takeback(two, 0).is_err());
+        game.accept_takeback(one, 0).unwrap();
+        assert_eq!(game.turn, 2);
+        assert_eq!(game.moves.len(), 1);
+        assert!(game.board[4].is_none());
+        assert!(game.current_player().unwrap() == two);
+
+        // Playing on withdraws the request
+        play_all(&mut game, &[(2, 2)]);
+        game.request_takeback(two).unwrap();
+        play_all(&mut game, &[(0, 1)]);
+        assert!(game.accept_takeback(one, 0).is_err());
+
+        // Taking back the winning move reopens the game
+        play_all(&mut game, &[(2, 0), (0, 2)]);
+        assert!(game.state == GameState::Won { winner: one });
+        game.request_takeback(one).unwrap();
+        game.accept_takeback(two, 0).unwrap();
+        assert!(game.is_active());
+        assert!(game.current_player().unwrap() == one);
+        game.resign(one).unwrap();
+        assert!(game.request_takeback(two).is_err());
+    }
+
+    #[test]
+    fn test_takeback_after_resignation() {
+        let mut game = new_game(3, 3);
+        let [one, two] = game.players;
+        play_all(&mut game, &[(0, 0)]);
+        game.request_takeback(one).unwrap();
+        game.resign(two).unwrap();
+        assert!(game.takeback_request.is_none());
+        assert!(game.accept_takeback(two, 0).is_err());
+
+        // Even a request left standing can't reopen the game
+        game.takeback_request = Some(one);
+        assert!(game.accept_takeback(two, 0).is_err());
+        assert!(game.state == GameState::Resigned { winner: one });
+        assert_eq!(game.moves.len(), 1);
+    }
+
+    #[test]
+    fn test_takeback_withdraws_rematch_offer() {
+        let mut game = new_game(3, 3);
+        let [one, two] = game.players;
+        play_all(&mut game, &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)]);
+        game.rematch(one, 0).unwrap();
+        game.request_takeback(one).unwrap();
+        game.accept_takeback(two, 0).unwrap();
+        assert!(game.rematch_offer.is_none());
+
+        // Finishing the game again takes a fresh offer from player one
+        play_all(&mut game, &[(0, 2)]);
+        assert!(!game.rematch(two, 0).unwrap());
+        assert!(game.rematch_offer == Some(two));
+    }
+
+    #[test]
+    fn test_rematch() {
+        let mut game = new_game(3, 3);
+        let [one, two] = game.players;
+        assert!(game.rematch(one, 0).is_err());
+        play_all(&mut game, &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)]);
+        game.rating_changes = [16, -16];
+
+        // Offering again changes nothing, it takes the opponent to accept
+        assert!(!game.rematch(one, 0).unwrap());
+        assert!(!game.rematch(one, 0).unwrap());
+        assert!(game.rematch(two, 5).unwrap());
+        assert!(game.is_active());
+        assert_eq!(game.round(), 1);
+        assert_eq!(game.turn, 1);
+        assert!(game.moves.is_empty());
+        assert!(game.board.iter().all(Option::is_none));
+        assert_eq!(game.rating_changes, [0, 0]);
+        assert_eq!(game.last_move_at, 5);
+        assert!(game.rematch_offer.is_none());
+
+        // Player two plays X this time
+        assert!(game.current_player().unwrap() == two);
+        play_all(&mut game, &[(0, 0)]);
+        assert!(game.board[0] == Some(Sign::X));
+        assert!(game.moves[0].player == 1);
+        game.resign(one).unwrap();
+        game.rematch(one, 0).unwrap();
+        game.rematch(two, 0).unwrap();
+        assert!(game.current_player().unwrap() == one);
+        assert_eq!(game.round(), 2);
+    }
+
+    #[test]
+    fn test_rematch_with_random_first_player() {
+        let mut game = new_game(3, 3);
+        game.config.first_player = FirstPlayer::Random;
+        game.config.move_timeout = 10;
+        let [one, two] = game.players;
+        commit_and_reveal(&mut game, [[1; 32], [2; 32]]);
+        assert!(game.current_player().unwrap() == two);
+        game.resign(one).unwrap();
+        game.rematch(one, 0).unwrap();
+        game.rematch(two, 0).unwrap();
+
+        // The last round's secrets would otherwise decide this one too
+        assert!(!game.is_first_player_decided());
+        assert_eq!(game.commitments, [None; 2]);
+        assert!(game.current_player().is_err());
+        commit_and_reveal(&mut game, [[1; 32], [3; 32]]);
+        assert!(game.current_player().unwrap() == one);
+    }
+
+    #[test]
+    fn test_takeback_in_ultimate() {
+        let mut game = new_game_with_mode(3, 3, GameMode::Ultimate);
+        let [one, two] = game.players;
+        // The third move wins the first sub-board and sends O anywhere
+        play_all(&mut game, &ULTIMATE_WIN[..13]);
+        assert!(game.next_sub_board == Some(5));
+        game.request_takeback(one).unwrap();
+        game.accept_takeback(two, 0).unwrap();
+        assert!(game.meta_board[0].is_none());
+        assert!(game.next_sub_board == Some(0));
+    }
+
+    #[test]
+    fn test_program_replies() {
+        let mut game = new_game(3, 3);
+        game.players[1] = ai::program_player();
+        game.play(&Tile(0, 0), 0).unwrap();
+        assert_eq!(game.turn, 3);
+        assert_eq!(game.moves[1].player, 1);
+        // The only reply to a corner that doesn't lose is the center
+        assert!(game.board[4] == Some(Sign::O));
+
+        let player = Pubkey::new_unique();
+        let config = GameConfig {
+            board_size: 4,
+            ..GameConfig::default()
+        };
+        let mut game = new_game(3, 3);
+        game.turn = 0;
+        assert!(game
+            .start(player, player, Some(ai::program_player()), config, None, 0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_ultimate_needs_classic_sub_boards() {
+        let mut game = new_game(3, 3);
+        game.turn = 0;
+        let config = GameConfig {
+            board_size: 4,
+            win_length: 3,
+            mode: GameMode::Ultimate,
+            ..GameConfig::default()
+        };
+        assert!(game
+            .start(Pubkey::default(), Pubkey::default(), None, config, None, 0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_cube_lines() {
+        let lines = [
+            [(0, 0, 0), (1, 1, 1), (2, 2, 2), (3, 3, 3)], // space diagonal
+            [(0, 3, 0), (1, 3, 1), (2, 3, 2), (3, 3, 3)], // diagonal through the layers
+            [(0, 1, 2), (1, 1, 2), (2, 1, 2), (3, 1, 2)], // straight through the layers
+        ];
+        // Player two's moves, on the first layer and away from all the lines
+        let replies = [(0, 2, 0), (0, 2, 1), (0, 2, 3)];
+        for line in lines {
+            let mut game = new_game_with_mode(4, 4, GameMode::Cube);
+            for (i, (layer, row, column)) in line.into_iter().enumerate() {
+                game.play_cube(&CubeTile(layer, row, column), 0).unwrap();
+                if let Some(&(layer, row, column)) = replies.get(i) {
+                    game.play_cube(&CubeTile(layer, row, column), 0).unwrap();
+                }
+            }
+            assert!(
+                game.state
+                    == GameState::Won {
+                        winner: game.players[0]
+                    }
+            );
+        }
+    }
+
+    #[test]
+    fn test_all_cube_lines_win() {
+        let mut lines = vec![];
+        for start in 0..64 {
+            let start = [start / 16, start / 4 % 4, start % 4];
+            for step in (0..27)
+                .map(|i| [i / 9 - 1, i / 3 % 3 - 1, i % 3 - 1])
+                .filter(|step| *step > [0, 0, 0])
+            {
+                let line: Vec<[isize; 3]> = (0..4)
+                    .map(|i| [0, 1, 2].map(|axis| start[axis] + step[axis] * i))
+                    .collect();
+                if line.iter().flatten().all(|c| (0..4).contains(c)) {
+                    lines.push(line);
+                }
+            }
+        }
+        assert_eq!(lines.len(), 76);
+
+        for line in lines {
+            let mut tiles = vec![None; 64];
+            for &[layer, row, col] in &line {
+                tiles[(layer * 16 + row * 4 + col) as usize] = Some(Sign::X);
+            }
+            for &tile in &line {
+                assert!(is_winning_cube_line(&tiles, tile.map(|c| c as u8)));
+            }
+            // Three of the four is not enough
+            let [layer, row, col] = line[0];
+            tiles[(layer * 16 + row * 4 + col) as usize] = None;
+            assert!(!is_winning_cube_line(&tiles, line[1].map(|c| c as u8)));
+        }
+    }
+
+    #[test]
+    fn test_cube_mode_only_takes_cube_tiles() {
+        let mut game = new_game_with_mode(4, 4, GameMode::Cube);
+        assert!(game.play(&Tile(0, 0), 0).is_err());
+        assert!(game.play_cube(&CubeTile(4, 0, 0), 0).is_err());
+        game.play_cube(&CubeTile(3, 3, 3), 0).unwrap();
+        assert_eq!(game.last_move().unwrap().layer, 3);
+
+        let mut game = new_game(3, 3);
+        assert!(game.play_cube(&CubeTile(0, 0, 0), 0).is_err());
+    }
+}
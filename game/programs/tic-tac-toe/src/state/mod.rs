@@ -1,3 +1,13 @@
+pub use config::*;
 pub use game::*;
+pub use player_profile::*;
+pub use queue::*;
+pub use series::*;
+pub use tournament::*;
 
+pub mod config;
 pub mod game;
+pub mod player_profile;
+pub mod queue;
+pub mod series;
+pub mod tournament;
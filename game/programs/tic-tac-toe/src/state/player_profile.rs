@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::elo::INITIAL_RATING;
+
+pub const PROFILE_SEED: &[u8] = b"profile";
+
+#[account]
+pub struct PlayerProfile {
+    player: Pubkey, // 32
+    wins: u32,      // 4
+    losses: u32,    // 4
+    ties: u32,      // 4
+    rating: u32,    // 4
+}
+
+impl PlayerProfile {
+    pub const MAXIMUM_SIZE: usize = 32 + 4 + 4 + 4 + 4;
+
+    /// Profiles are created on demand, this fills in a profile that was
+    /// just allocated and leaves existing ones alone.
+    pub fn initialize(&mut self, player: Pubkey) {
+        if self.player == Pubkey::default() {
+            self.player = player;
+            self.rating = INITIAL_RATING;
+        }
+    }
+
+    pub fn player(&self) -> Pubkey {
+        self.player
+    }
+
+    pub fn rating(&self) -> u32 {
+        self.rating
+    }
+
+    /// The rating stored in `profile`, which may not have been created yet
+    /// for players who haven't finished a game.
+    pub fn rating_of(profile: &AccountInfo) -> Result<u32> {
+        if profile.owner == &crate::ID && !profile.data_is_empty() {
+            Ok(Self::try_deserialize(&mut &profile.try_borrow_data()?[..])?.rating())
+        } else {
+            Ok(INITIAL_RATING)
+        }
+    }
+
+    /// Records the outcome of a finished game, `winner` being `None` for a
+    /// tie.
+    pub fn record(&mut self, winner: Option<Pubkey>, rating_change: i32) {
+        match winner {
+            Some(winner) if winner == self.player => self.wins += 1,
+            Some(_) => self.losses += 1,
+            None => self.ties += 1,
+        }
+        self.rating = self.rating.saturating_add_signed(rating_change);
+    }
+
+    /// Undoes `record` for a game whose last move was taken back
+    pub fn unrecord(&mut self, winner: Option<Pubkey>, rating_change: i32) {
+        match winner {
+            Some(winner) if winner == self.player => self.wins -= 1,
+            Some(_) => self.losses -= 1,
+            None => self.ties -= 1,
+        }
+        self.rating = self.rating.saturating_add_signed(-rating_change);
+    }
+}
@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+
+use crate::{ai, errors::TicTacToeError, state::game::GameConfig};
+
+pub const QUEUE_SEED: &[u8] = b"queue";
+
+/// Players waiting to be paired up for a game with the queue's config. Each
+/// of them deposits the wager into the queue, which moves into the game once
+/// they are paired. Games are PDAs of the queue and the number of games it
+/// set up before them.
+#[account]
+pub struct Queue {
+    config: GameConfig,       // 8 + 8 + 1 + 1 + 1 + 1 + 1 + 8 + 8, used for every game
+    games_created: u64,       // 8, also the index of the next game
+    entries: Vec<QueueEntry>, // 4 + (MAX_ENTRIES * QueueEntry::SIZE), oldest first
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct QueueEntry {
+    pub player: Pubkey,    // 32
+    pub rating: u32,       // 4, when enqueueing
+    pub band: Option<u32>, // 1 + 4, the largest rating difference accepted
+}
+
+impl QueueEntry {
+    pub const SIZE: usize = 32 + 4 + (1 + 4);
+
+    fn accepts(&self, other: &QueueEntry) -> bool {
+        self.band
+            .map_or(true, |band| self.rating.abs_diff(other.rating) <= band)
+    }
+}
+
+impl Queue {
+    pub const MAX_ENTRIES: usize = 32;
+
+    pub const MAXIMUM_SIZE: usize =
+        (8 + 8 + 1 + 1 + 1 + 1 + 1 + 8 + 8) + 8 + (4 + (Self::MAX_ENTRIES * QueueEntry::SIZE));
+
+    pub fn start(&mut self, config: GameConfig) -> Result<()> {
+        config.validate()?;
+        self.config = config;
+        Ok(())
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn games_created(&self) -> u64 {
+        self.games_created
+    }
+
+    pub fn entries(&self) -> &[QueueEntry] {
+        &self.entries
+    }
+
+    pub fn enqueue(&mut self, player: Pubkey, rating: u32, band: Option<u32>) -> Result<()> {
+        // The program can't sign for the games it would play X in
+        require_keys_neq!(
+            player,
+            ai::program_player(),
+            TicTacToeError::UnsupportedAgainstProgram
+        );
+        require!(
+            self.entries.iter().all(|entry| entry.player != player),
+            TicTacToeError::AlreadyQueued
+        );
+        require!(
+            self.entries.len() < Self::MAX_ENTRIES,
+            TicTacToeError::QueueFull
+        );
+        self.entries.push(QueueEntry {
+            player,
+            rating,
+            band,
+        });
+        Ok(())
+    }
+
+    pub fn dequeue(&mut self, player: Pubkey) -> Result<()> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.player == player)
+            .ok_or(TicTacToeError::NotQueued)?;
+        self.entries.remove(index);
+        Ok(())
+    }
+
+    /// Takes the first two entries within each other's rating band out of
+    /// the queue, the one that has waited longer playing X, and counts the
+    /// game they are about to play.
+    pub fn pair(&mut self) -> Result<[Pubkey; 2]> {
+        let (first, second) = (1..self.entries.len())
+            .flat_map(|second| (0..second).map(move |first| (first, second)))
+            .find(|&(first, second)| {
+                let [one, two] = [self.entries[first], self.entries[second]];
+                one.accepts(&two) && two.accepts(&one)
+            })
+            .ok_or(TicTacToeError::NoMatchInQueue)?;
+        let players = [self.entries[first].player, self.entries[second].player];
+        self.entries.remove(second);
+        self.entries.remove(first);
+        self.games_created += 1;
+        Ok(players)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue() {
+        let mut queue = Queue {
+            config: GameConfig::default(),
+            games_created: 0,
+            entries: vec![],
+        };
+        // No board to play on
+        assert!(queue.start(GameConfig::default()).is_err());
+        queue
+            .start(GameConfig {
+                board_size: 3,
+                win_length: 3,
+                ..GameConfig::default()
+            })
+            .unwrap();
+        let players: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        assert!(queue.pair().is_err());
+
+        // Whoever has a band only accepts players close to their own rating
+        queue.enqueue(players[0], 1000, Some(100)).unwrap();
+        queue.enqueue(players[1], 1400, Some(500)).unwrap();
+        assert!(queue.pair().is_err());
+        assert!(queue.enqueue(players[1], 1400, None).is_err());
+        queue.enqueue(players[2], 1200, Some(100)).unwrap();
+        assert!(queue.pair().is_err());
+        queue.enqueue(players[3], 1050, None).unwrap();
+        assert_eq!(queue.pair().unwrap(), [players[0], players[3]]);
+        assert_eq!(queue.games_created(), 1);
+        assert_eq!(queue.entries().len(), 2);
+
+        assert!(queue.dequeue(players[0]).is_err());
+        queue.dequeue(players[2]).unwrap();
+        queue.enqueue(players[0], 1000, None).unwrap();
+        assert_eq!(queue.pair().unwrap(), [players[1], players[0]]);
+        assert_eq!(queue.games_created(), 2);
+        assert!(queue.entries().is_empty());
+
+        assert!(queue.enqueue(ai::program_player(), 1000, None).is_err());
+        for _ in 0..Queue::MAX_ENTRIES {
+            queue.enqueue(Pubkey::new_unique(), 1000, Some(0)).unwrap();
+        }
+        assert!(queue.enqueue(Pubkey::new_unique(), 1000, None).is_err());
+    }
+}
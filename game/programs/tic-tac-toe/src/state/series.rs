@@ -0,0 +1,249 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    ai,
+    errors::TicTacToeError,
+    state::game::{GameConfig, GameState},
+};
+
+/// A best-of-N match between two players. Its games are PDAs of the series
+/// and their index, and who plays X alternates from one game to the next.
+#[account]
+pub struct Series {
+    players: [Pubkey; 2],         // (32 * 2)
+    best_of: u8,                  // 1, always odd
+    config: GameConfig,           // 8 + 8 + 1 + 1 + 1 + 1 + 1 + 8 + 8, used for every game
+    games_played: u32,            // 4, also the index of the next game
+    wins: [u8; 2],                // 1 * 2, ties don't count and are replayed
+    current_game: Option<Pubkey>, // 1 + 32, started but not yet scored
+    winner: Option<Pubkey>,       // 1 + 32
+    last_scored_at: i64,          // 8, when the series was created or last scored a game
+}
+
+impl Series {
+    pub const MAXIMUM_SIZE: usize =
+        (32 * 2) + 1 + (8 + 8 + 1 + 1 + 1 + 1 + 1 + 8 + 8) + 4 + 2 + (1 + 32) + (1 + 32) + 8;
+
+    pub fn start(
+        &mut self,
+        players: [Pubkey; 2],
+        best_of: u8,
+        config: GameConfig,
+        now: i64,
+    ) -> Result<()> {
+        require!(best_of % 2 == 1, TicTacToeError::InvalidBestOf);
+        require_keys_neq!(players[0], players[1], TicTacToeError::CannotJoinOwnGame);
+        // The program can't sign for the games it would play X in
+        require_keys_neq!(
+            players[1],
+            ai::program_player(),
+            TicTacToeError::UnsupportedAgainstProgram
+        );
+        config.validate()?;
+        self.players = players;
+        self.best_of = best_of;
+        self.config = config;
+        self.last_scored_at = now;
+        Ok(())
+    }
+
+    pub fn players(&self) -> [Pubkey; 2] {
+        self.players
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn games_played(&self) -> u32 {
+        self.games_played
+    }
+
+    pub fn winner(&self) -> Option<Pubkey> {
+        self.winner
+    }
+
+    /// The seats of the next game, player one of the series playing X in the
+    /// first game and every other game after that
+    pub fn next_players(&self) -> Result<[Pubkey; 2]> {
+        require!(self.winner.is_none(), TicTacToeError::SeriesOver);
+        require!(
+            self.current_game.is_none(),
+            TicTacToeError::SeriesGameInProgress
+        );
+        let first = (self.games_played % 2) as usize;
+        Ok([self.players[first], self.players[1 - first]])
+    }
+
+    pub fn start_game(&mut self, game: Pubkey) -> Result<()> {
+        self.count_game()?;
+        self.current_game = Some(game);
+        Ok(())
+    }
+
+    fn count_game(&mut self) -> Result<()> {
+        // Ties are replayed for as long as the players keep tying
+        self.games_played = self
+            .games_played
+            .checked_add(1)
+            .ok_or(TicTacToeError::TooManySeriesGames)?;
+        Ok(())
+    }
+
+    /// Scores the current game once it is over, ending the series as soon as
+    /// a player has won more than half of `best_of`.
+    pub fn record(&mut self, game: Pubkey, outcome: &GameState, now: i64) -> Result<()> {
+        require!(
+            self.current_game == Some(game),
+            TicTacToeError::WrongSeriesGame
+        );
+        require!(
+            !matches!(outcome, GameState::Active | GameState::WaitingForOpponent),
+            TicTacToeError::GameNotOver
+        );
+        self.current_game = None;
+        self.last_scored_at = now;
+        if let Some(winner) = outcome.winner() {
+            self.add_win(winner);
+        }
+        Ok(())
+    }
+
+    /// Gives the next game to the player due to play O in it once the player
+    /// due to play X has let the move timeout pass without starting it.
+    /// Without a move timeout, a series waits on its players like its games
+    /// do.
+    pub fn claim_forfeit(&mut self, player: Pubkey, now: i64) -> Result<()> {
+        let [_, claimant] = self.next_players()?;
+        require_keys_eq!(player, claimant, TicTacToeError::NotPlayersTurn);
+        require!(self.config.move_timeout > 0, TicTacToeError::NoMoveTimeout);
+        require_gte!(
+            now,
+            self.last_scored_at + self.config.move_timeout,
+            TicTacToeError::TimeoutNotReached
+        );
+        self.count_game()?;
+        self.last_scored_at = now;
+        self.add_win(player);
+        Ok(())
+    }
+
+    fn add_win(&mut self, winner: Pubkey) {
+        let index = (winner == self.players[1]) as usize;
+        self.wins[index] += 1;
+        if self.wins[index] > self.best_of / 2 {
+            self.winner = Some(winner);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classic() -> GameConfig {
+        GameConfig {
+            board_size: 3,
+            win_length: 3,
+            ..GameConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_best_of_three() {
+        let players = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut series = Series {
+            players: [Pubkey::default(); 2],
+            best_of: 0,
+            config: GameConfig::default(),
+            games_played: 0,
+            wins: [0, 0],
+            current_game: None,
+            winner: None,
+            last_scored_at: 0,
+        };
+        assert!(series.start(players, 2, classic(), 0).is_err());
+        // No board to play on
+        assert!(series.start(players, 3, GameConfig::default(), 0).is_err());
+        series.start(players, 3, classic(), 0).unwrap();
+
+        // Player two wins the first game, then a tie is replayed
+        let outcomes = [
+            GameState::Won { winner: players[1] },
+            GameState::Tie,
+            GameState::Won { winner: players[0] },
+            GameState::Resigned { winner: players[0] },
+        ];
+        for (index, outcome) in outcomes.iter().enumerate() {
+            let seats = series.next_players().unwrap();
+            assert_eq!(seats[0], players[index % 2]);
+            let game = Pubkey::new_unique();
+            series.start_game(game).unwrap();
+            assert!(series.next_players().is_err());
+            assert!(series.record(game, &GameState::Active, 0).is_err());
+            assert!(series.record(Pubkey::new_unique(), outcome, 0).is_err());
+            series.record(game, outcome, 0).unwrap();
+        }
+        assert_eq!(series.wins, [2, 1]);
+        assert_eq!(series.winner(), Some(players[0]));
+        assert!(series.next_players().is_err());
+    }
+
+    #[test]
+    fn test_endless_ties() {
+        let mut series = Series {
+            players: [Pubkey::new_unique(), Pubkey::new_unique()],
+            best_of: 3,
+            config: GameConfig::default(),
+            games_played: u32::MAX - 1,
+            wins: [0, 0],
+            current_game: None,
+            winner: None,
+            last_scored_at: 0,
+        };
+        let game = Pubkey::new_unique();
+        series.start_game(game).unwrap();
+        series.record(game, &GameState::Tie, 0).unwrap();
+        assert!(series.start_game(game).is_err());
+    }
+
+    #[test]
+    fn test_forfeit() {
+        let players = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut series = Series {
+            players: [Pubkey::default(); 2],
+            best_of: 0,
+            config: GameConfig::default(),
+            games_played: 0,
+            wins: [0, 0],
+            current_game: None,
+            winner: None,
+            last_scored_at: 0,
+        };
+        let config = GameConfig {
+            move_timeout: 60,
+            ..classic()
+        };
+        series.start(players, 3, config, 100).unwrap();
+
+        // Player one is due to play X and doesn't start the game
+        assert!(series.claim_forfeit(players[0], 1_000).is_err());
+        assert!(series.claim_forfeit(players[1], 159).is_err());
+        series.claim_forfeit(players[1], 160).unwrap();
+        assert_eq!(series.wins, [0, 1]);
+        assert_eq!(series.games_played(), 1);
+
+        // Now it is up to player two, and a started game can't be forfeited
+        assert!(series.claim_forfeit(players[1], 220).is_err());
+        let game = Pubkey::new_unique();
+        series.start_game(game).unwrap();
+        assert!(series.claim_forfeit(players[0], 1_000).is_err());
+        series
+            .record(game, &GameState::Won { winner: players[0] }, 1_000)
+            .unwrap();
+        assert!(series.claim_forfeit(players[1], 1_059).is_err());
+        series.claim_forfeit(players[1], 1_060).unwrap();
+        assert_eq!(series.winner(), Some(players[1]));
+        assert!(series.claim_forfeit(players[0], 2_000).is_err());
+    }
+}
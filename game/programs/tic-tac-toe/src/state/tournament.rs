@@ -0,0 +1,241 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::TicTacToeError,
+    state::game::{GameConfig, GameState},
+};
+
+/// A single-elimination tournament. Registration closes once the bracket is
+/// full, and the games of a round are PDAs of the tournament, the round and
+/// the match's index in it.
+#[account]
+pub struct Tournament {
+    authority: Pubkey,      // 32, who created the tournament
+    size: u8,               // 1, players in the bracket: 8, 16 or 32
+    config: GameConfig,     // 8 + 8 + 1 + 1 + 1 + 1 + 1 + 8 + 8, used for every game
+    entrants: Vec<Entrant>, // 4 + (size * Entrant::SIZE), in order of registration
+    round: u8,              // 1, 0 while registration is open
+    matches: Vec<Match>,    // 4 + ((size / 2) * Match::SIZE), the current round only
+    winner: Option<Pubkey>, // 1 + 32
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct Entrant {
+    pub player: Pubkey, // 32
+    pub rating: u32,    // 4, when registering, decides the seeding
+}
+
+impl Entrant {
+    pub const SIZE: usize = 32 + 4;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct Match {
+    pub players: [Pubkey; 2],   // (32 * 2)
+    pub winner: Option<Pubkey>, // 1 + 32
+}
+
+impl Match {
+    pub const SIZE: usize = (32 * 2) + (1 + 32);
+}
+
+impl Tournament {
+    pub const SIZES: [u8; 3] = [8, 16, 32];
+
+    pub fn maximum_size(size: u8) -> usize {
+        let size = size as usize;
+        32 + 1
+            + (8 + 8 + 1 + 1 + 1 + 1 + 1 + 8 + 8)
+            + (4 + (size * Entrant::SIZE))
+            + 1
+            + (4 + ((size / 2) * Match::SIZE))
+            + (1 + 32)
+    }
+
+    pub fn start(&mut self, authority: Pubkey, size: u8, config: GameConfig) -> Result<()> {
+        require!(
+            Self::SIZES.contains(&size),
+            TicTacToeError::InvalidTournamentSize
+        );
+        // Games are set up by whoever pays for them, nobody puts in a deposit
+        require!(config.wager == 0, TicTacToeError::TournamentWager);
+        config.validate()?;
+        self.authority = authority;
+        self.size = size;
+        self.config = config;
+        Ok(())
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn round(&self) -> u8 {
+        self.round
+    }
+
+    pub fn winner(&self) -> Option<Pubkey> {
+        self.winner
+    }
+
+    /// Adds `player` to the bracket, which is drawn as soon as it is full.
+    pub fn register(&mut self, player: Pubkey, rating: u32) -> Result<()> {
+        require_eq!(self.round, 0, TicTacToeError::RegistrationClosed);
+        require!(
+            self.entrants.iter().all(|entrant| entrant.player != player),
+            TicTacToeError::AlreadyRegistered
+        );
+        self.entrants.push(Entrant { player, rating });
+        if self.entrants.len() == self.size as usize {
+            self.draw_bracket();
+        }
+        Ok(())
+    }
+
+    /// Seeds the entrants by rating, earlier registrations first among equal
+    /// ratings, and pairs them so the top seeds can only meet late.
+    fn draw_bracket(&mut self) {
+        let mut seeds = self.entrants.clone();
+        seeds.sort_by_key(|entrant| std::cmp::Reverse(entrant.rating));
+        self.matches = seed_order(self.size as usize)
+            .chunks(2)
+            .map(|pair| Match {
+                players: [seeds[pair[0]].player, seeds[pair[1]].player],
+                winner: None,
+            })
+            .collect();
+        self.round = 1;
+    }
+
+    /// The players of an undecided match of the current round
+    pub fn match_players(&self, index: u8) -> Result<[Pubkey; 2]> {
+        require_gt!(self.round, 0, TicTacToeError::TournamentNotStarted);
+        let m = self
+            .matches
+            .get(index as usize)
+            .ok_or(TicTacToeError::InvalidMatch)?;
+        require!(m.winner.is_none(), TicTacToeError::MatchDecided);
+        Ok(m.players)
+    }
+
+    /// Records the outcome of a match's game, returning whether it was a
+    /// tie that has to be replayed. Once every match of the round is
+    /// decided the winners are paired up for the next one.
+    pub fn record(&mut self, index: u8, outcome: &GameState) -> Result<bool> {
+        self.match_players(index)?;
+        require!(
+            !matches!(outcome, GameState::Active | GameState::WaitingForOpponent),
+            TicTacToeError::GameNotOver
+        );
+        let Some(winner) = outcome.winner() else {
+            return Ok(true);
+        };
+        self.matches[index as usize].winner = Some(winner);
+
+        let winners: Option<Vec<Pubkey>> = self.matches.iter().map(|m| m.winner).collect();
+        match winners {
+            Some(winners) if winners.len() == 1 => self.winner = Some(winners[0]),
+            Some(winners) => {
+                self.matches = winners
+                    .chunks(2)
+                    .map(|pair| Match {
+                        players: [pair[0], pair[1]],
+                        winner: None,
+                    })
+                    .collect();
+                self.round += 1;
+            }
+            None => {}
+        }
+        Ok(false)
+    }
+}
+
+/// Bracket positions of seeds 0 to `size - 1`, reading off consecutive
+/// pairs as first round matches. Every match is between seeds adding up to
+/// `size - 1`, and each half of the bracket is seeded the same way.
+fn seed_order(size: usize) -> Vec<usize> {
+    let mut order = vec![0];
+    while order.len() < size {
+        let seeds = order.len() * 2;
+        order = order
+            .into_iter()
+            .flat_map(|seed| [seed, seeds - 1 - seed])
+            .collect();
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_order() {
+        assert_eq!(seed_order(8), vec![0, 7, 3, 4, 1, 6, 2, 5]);
+        let order = seed_order(32);
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_eight_player_tournament() {
+        let mut tournament = Tournament {
+            authority: Pubkey::default(),
+            size: 0,
+            config: GameConfig::default(),
+            entrants: vec![],
+            round: 0,
+            matches: vec![],
+            winner: None,
+        };
+        let config = GameConfig {
+            board_size: 3,
+            win_length: 3,
+            ..GameConfig::default()
+        };
+        assert!(tournament.start(Pubkey::default(), 6, config).is_err());
+        // No board to play on
+        assert!(tournament
+            .start(Pubkey::default(), 8, GameConfig::default())
+            .is_err());
+        tournament.start(Pubkey::default(), 8, config).unwrap();
+
+        // Registered from the weakest to the strongest player
+        let players: Vec<Pubkey> = (0..8).map(|_| Pubkey::new_unique()).collect();
+        for (rating, player) in players.iter().enumerate() {
+            assert!(tournament.match_players(0).is_err());
+            tournament.register(*player, 1000 + rating as u32).unwrap();
+        }
+        assert!(tournament.register(players[0], 1000).is_err());
+        assert!(tournament.register(Pubkey::new_unique(), 1000).is_err());
+        assert_eq!(tournament.round(), 1);
+        assert_eq!(
+            tournament.match_players(0).unwrap(),
+            [players[7], players[0]]
+        );
+
+        // The better seed wins every match, except for one tie that is
+        // replayed first
+        assert!(tournament.record(0, &GameState::Active).is_err());
+        assert!(tournament.record(1, &GameState::Tie).unwrap());
+        while tournament.winner().is_none() {
+            let round = tournament.round();
+            for index in 0..tournament.matches.len() as u8 {
+                let [one, two] = tournament.match_players(index).unwrap();
+                let rating = |p| players.iter().position(|&q| q == p).unwrap();
+                let winner = if rating(one) > rating(two) { one } else { two };
+                assert!(!tournament
+                    .record(index, &GameState::Won { winner })
+                    .unwrap());
+            }
+            if tournament.winner().is_none() {
+                assert_eq!(tournament.round(), round + 1);
+            }
+        }
+        assert_eq!(tournament.round(), 3);
+        assert_eq!(tournament.winner(), Some(players[7]));
+        assert!(tournament.match_players(0).is_err());
+    }
+}
@@ -2,31 +2,192 @@ import * as anchor from '@coral-xyz/anchor';
 import { AnchorProvider, Provider, Wallet } from '@coral-xyz/anchor';
 import type { TicTacToe } from '../target/types/tic_tac_toe';
 import chai, { expect } from 'chai';
+import { createHash } from 'crypto';
 import chaiAsPromised from 'chai-as-promised';
 
 chai.use(chaiAsPromised);
 
 type Tile = [number, number]; // (x, y) coordinates for a play
+type CubeTile = [number, number, number]; // (layer, row, column) in a cube game
 
-type GameState = { active: object } | { tie: object } | { won: { winner: anchor.web3.PublicKey } };
+type GameState =
+  | { active: object }
+  | { tie: object }
+  | { won: { winner: anchor.web3.PublicKey } }
+  | { resigned: { winner: anchor.web3.PublicKey } }
+  | { timedOut: { winner: anchor.web3.PublicKey } }
+  | { waitingForOpponent: object };
 
 const ACTIVE_STATE: GameState = { active: {} };
 const TIE_STATE: GameState = { tie: {} };
+const WAITING_STATE: GameState = { waitingForOpponent: {} };
 
 type Sign = { x: object } | { o: object } | null;
 
-// prettier-ignore
-type Board = [
-  [Sign, Sign, Sign],
-  [Sign, Sign, Sign],
-  [Sign, Sign, Sign]
-];
+type Board = Sign[]; // row-major, boardSize * boardSize tiles
+
+type GameMode = { classic: object } | { ultimate: object } | { cube: object };
+
+type Rules = { normal: object } | { misere: object };
+
+type FirstPlayer = { playerOne: object } | { random: object };
+
+type GameConfig = {
+  wager: anchor.BN;
+  moveTimeout: anchor.BN;
+  boardSize: number;
+  winLength: number;
+  mode: GameMode;
+  rules: Rules;
+  firstPlayer: FirstPlayer;
+  clock: anchor.BN;
+  increment: anchor.BN;
+};
+
+function gameConfig(
+  wager: number = 0,
+  moveTimeout: number = 0,
+  boardSize: number = 3,
+  winLength: number = 3,
+  mode: GameMode = { classic: {} },
+  rules: Rules = { normal: {} },
+  firstPlayer: FirstPlayer = { playerOne: {} },
+  clock: number = 0,
+  increment: number = 0,
+): GameConfig {
+  return {
+    wager: new anchor.BN(wager),
+    moveTimeout: new anchor.BN(moveTimeout),
+    boardSize,
+    winLength,
+    mode,
+    rules,
+    firstPlayer,
+    clock: new anchor.BN(clock),
+    increment: new anchor.BN(increment),
+  };
+}
+
+function tileCount(config: GameConfig): number {
+  if ('ultimate' in config.mode) {
+    return 9 * 9;
+  }
+  if ('cube' in config.mode) {
+    return 4 * 4 * 4;
+  }
+  return config.boardSize * config.boardSize;
+}
+
+// Layout version of the `Game` account
+const GAME_VERSION = 4;
 
 type GameAccount = {
+  version: number;
   players: [anchor.web3.PublicKey, anchor.web3.PublicKey];
   turn: number;
   state: GameState;
   board: Board;
+  config: GameConfig;
+  wagerMatched: boolean;
+  payer: anchor.web3.PublicKey;
+  lastMoveAt: anchor.BN;
+  ratingChanges: [number, number];
+  drawOffer: anchor.web3.PublicKey | null;
+  moves: Move[];
+  mint: anchor.web3.PublicKey | null;
+  metaBoard: Sign[];
+  nextSubBoard: number | null;
+  firstPlayer: number;
+  commitments: (number[] | null)[];
+  secrets: (number[] | null)[];
+  remainingTime: anchor.BN[];
+  takebackRequest: anchor.web3.PublicKey | null;
+  rematchOffer: anchor.web3.PublicKey | null;
+  round: number;
+  owner: anchor.web3.PublicKey | null;
+  scored: boolean;
+};
+
+type Move = {
+  tile: Tile;
+  player: number;
+  layer: number;
+};
+
+// The program's own seat in a single-player game
+function programPlayer(programId: anchor.web3.PublicKey): anchor.web3.PublicKey {
+  const [address] = anchor.web3.PublicKey.findProgramAddressSync([Buffer.from('ai')], programId);
+  return address;
+}
+
+function profileAddress(
+  programId: anchor.web3.PublicKey,
+  player: anchor.web3.PublicKey,
+): anchor.web3.PublicKey {
+  const [address] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('profile'), player.toBuffer()],
+    programId,
+  );
+  return address;
+}
+
+function gamePda(
+  programId: anchor.web3.PublicKey,
+  playerOne: anchor.web3.PublicKey,
+  playerTwo: anchor.web3.PublicKey | null,
+  nonce: number,
+): anchor.web3.PublicKey {
+  const [address] = anchor.web3.PublicKey.findProgramAddressSync(
+    [
+      Buffer.from('game'),
+      playerOne.toBuffer(),
+      (playerTwo ?? anchor.web3.PublicKey.default).toBuffer(),
+      new anchor.BN(nonce).toArrayLike(Buffer, 'le', 8),
+    ],
+    programId,
+  );
+  return address;
+}
+
+// Address of game `index` of a series, counting from 0
+function seriesGame(
+  programId: anchor.web3.PublicKey,
+  series: anchor.web3.PublicKey,
+  index: number,
+): anchor.web3.PublicKey {
+  const [address] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('game'), series.toBuffer(), new anchor.BN(index).toArrayLike(Buffer, 'le', 4)],
+    programId,
+  );
+  return address;
+}
+
+function queuePda(programId: anchor.web3.PublicKey, id: number): anchor.web3.PublicKey {
+  const [address] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('queue'), new anchor.BN(id).toArrayLike(Buffer, 'le', 8)],
+    programId,
+  );
+  return address;
+}
+
+function queueGame(
+  programId: anchor.web3.PublicKey,
+  queue: anchor.web3.PublicKey,
+  index: number,
+): anchor.web3.PublicKey {
+  const [address] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('game'), queue.toBuffer(), new anchor.BN(index).toArrayLike(Buffer, 'le', 8)],
+    programId,
+  );
+  return address;
+}
+
+type PlayerProfile = {
+  player: anchor.web3.PublicKey;
+  wins: number;
+  losses: number;
+  ties: number;
+  rating: number;
 };
 
 class Player {
@@ -46,7 +207,7 @@ class Player {
     return this.program.provider.publicKey;
   }
 
-  private async gameAccount(): Promise<GameAccount> {
+  public async gameAccount(): Promise<GameAccount> {
     return (await this.program.account.game.fetch(this.gameID)) as GameAccount;
   }
 
@@ -62,7 +223,16 @@ class Player {
     );
   }
 
-  public async setupGame(gameKeypair: anchor.web3.Keypair, opponent: anchor.web3.PublicKey) {
+  public async profile(): Promise<PlayerProfile | null> {
+    const address = profileAddress(this.program.programId, this.pubkey());
+    return (await this.program.account.playerProfile.fetchNullable(address)) as PlayerProfile;
+  }
+
+  public async setupGame(
+    gameKeypair: anchor.web3.Keypair,
+    opponent: anchor.web3.PublicKey | null,
+    config: GameConfig = gameConfig(),
+  ) {
     // The private key is only needed by this function, but the public key
     // was saved during construction.
     expect(this.gameID).eql(gameKeypair.publicKey);
@@ -70,7 +240,7 @@ class Player {
     await this.printBalance('player one before setupGame');
 
     await this.program.methods
-      .setupGame(opponent)
+      .setupGame(opponent, config)
       .accounts({
         game: this.gameID,
         playerOne: this.pubkey(),
@@ -80,17 +250,256 @@ class Player {
 
     const gameState = await this.gameAccount();
     expect(gameState.turn).to.equal(1);
-    expect(gameState.players).to.eql([this.pubkey(), opponent]);
-    expect(gameState.state).to.eql(ACTIVE_STATE);
-    expect(gameState.board).to.eql([
-      [null, null, null],
-      [null, null, null],
-      [null, null, null],
-    ]);
+    if (opponent) {
+      expect(gameState.players).to.eql([this.pubkey(), opponent]);
+      expect(gameState.state).to.eql(ACTIVE_STATE);
+    } else {
+      expect(gameState.players).to.eql([this.pubkey(), anchor.web3.PublicKey.default]);
+      expect(gameState.state).to.eql(WAITING_STATE);
+    }
+    expect(gameState.version).to.equal(GAME_VERSION);
+    expect(gameState.board).to.eql(Array(tileCount(config)).fill(null));
+    expect(gameState.config).to.eql(config);
+    expect(gameState.wagerMatched).to.equal(false);
+    expect(gameState.payer).to.eql(this.pubkey());
 
     await this.printBalance('player one after setupGame');
   }
 
+  public async setupPdaGame(
+    opponent: anchor.web3.PublicKey | null,
+    nonce: number,
+    config: GameConfig = gameConfig(),
+  ) {
+    expect(this.gameID).eql(gamePda(this.program.programId, this.pubkey(), opponent, nonce));
+
+    await this.program.methods
+      .setupPdaGame(opponent, new anchor.BN(nonce), config)
+      .accounts({
+        playerOne: this.pubkey(),
+      })
+      .rpc();
+
+    const gameState = await this.gameAccount();
+    expect(gameState.turn).to.equal(1);
+    expect(gameState.config).to.eql(config);
+    expect(gameState.payer).to.eql(this.pubkey());
+  }
+
+  public async joinGame(): Promise<void> {
+    await this.program.methods
+      .joinGame()
+      .accounts({
+        game: this.gameID,
+        playerTwo: this.pubkey(),
+      })
+      .rpc();
+
+    const gameState = await this.gameAccount();
+    expect(gameState.players[1]).to.eql(this.pubkey());
+    expect(gameState.state).to.eql(ACTIVE_STATE);
+  }
+
+  public async matchWager(): Promise<void> {
+    await this.program.methods
+      .matchWager()
+      .accounts({
+        game: this.gameID,
+        playerTwo: this.pubkey(),
+      })
+      .rpc();
+
+    expect((await this.gameAccount()).wagerMatched).to.equal(true);
+  }
+
+  public async claimTimeout(): Promise<void> {
+    const gameBefore = await this.gameAccount();
+
+    await this.program.methods
+      .claimTimeout()
+      .accounts({
+        player: this.pubkey(),
+        game: this.gameID,
+        settlement: {
+          playerOne: gameBefore.players[0],
+          playerTwo: gameBefore.players[1],
+          payer: this.pubkey(),
+        },
+      })
+      .rpc();
+
+    const gameAfter = await this.gameAccount();
+    expect(gameAfter.state).to.eql({ timedOut: { winner: this.pubkey() } });
+  }
+
+  // Flags the player to move for running out of time, which anyone can do
+  public async flag(): Promise<void> {
+    const gameBefore = await this.gameAccount();
+
+    await this.program.methods
+      .flag()
+      .accounts({
+        game: this.gameID,
+        settlement: {
+          playerOne: gameBefore.players[0],
+          playerTwo: gameBefore.players[1],
+          payer: this.pubkey(),
+        },
+      })
+      .rpc();
+
+    const gameAfter = await this.gameAccount();
+    expect(gameAfter.state).to.have.property('timedOut');
+  }
+
+  public async resign(expectedState: GameState): Promise<void> {
+    const gameBefore = await this.gameAccount();
+
+    await this.program.methods
+      .resign()
+      .accounts({
+        player: this.pubkey(),
+        game: this.gameID,
+        settlement: {
+          playerOne: gameBefore.players[0],
+          playerTwo: gameBefore.players[1],
+          payer: this.pubkey(),
+        },
+      })
+      .rpc();
+
+    const gameAfter = await this.gameAccount();
+    expect(gameAfter.turn).to.equal(gameBefore.turn);
+    expect(gameAfter.board).to.eql(gameBefore.board);
+    expect(gameAfter.state).to.eql(expectedState);
+  }
+
+  public async commitSecret(secret: Buffer): Promise<void> {
+    const commitment = createHash('sha256')
+      .update(secret)
+      .update(this.pubkey().toBuffer())
+      .digest();
+    await this.program.methods
+      .commitSecret([...commitment])
+      .accounts({
+        player: this.pubkey(),
+        game: this.gameID,
+      })
+      .rpc();
+  }
+
+  public async revealSecret(secret: Buffer): Promise<void> {
+    await this.program.methods
+      .revealSecret([...secret])
+      .accounts({
+        player: this.pubkey(),
+        game: this.gameID,
+      })
+      .rpc();
+  }
+
+  public async offerDraw(): Promise<void> {
+    await this.program.methods
+      .offerDraw()
+      .accounts({
+        player: this.pubkey(),
+        game: this.gameID,
+      })
+      .rpc();
+
+    const gameAfter = await this.gameAccount();
+    expect(gameAfter.drawOffer).to.eql(this.pubkey());
+  }
+
+  public async acceptDraw(): Promise<void> {
+    const gameBefore = await this.gameAccount();
+
+    await this.program.methods
+      .acceptDraw()
+      .accounts({
+        player: this.pubkey(),
+        game: this.gameID,
+        settlement: {
+          playerOne: gameBefore.players[0],
+          playerTwo: gameBefore.players[1],
+          payer: this.pubkey(),
+        },
+      })
+      .rpc();
+
+    const gameAfter = await this.gameAccount();
+    expect(gameAfter.board).to.eql(gameBefore.board);
+    expect(gameAfter.state).to.eql({ tie: {} });
+    expect(gameAfter.drawOffer).to.be.null;
+  }
+
+  public async requestTakeback(): Promise<void> {
+    await this.program.methods
+      .requestTakeback()
+      .accounts({
+        player: this.pubkey(),
+        game: this.gameID,
+      })
+      .rpc();
+
+    const gameAfter = await this.gameAccount();
+    expect(gameAfter.takebackRequest).to.eql(this.pubkey());
+  }
+
+  public async acceptTakeback(): Promise<void> {
+    const gameBefore = await this.gameAccount();
+
+    await this.program.methods
+      .acceptTakeback()
+      .accounts({
+        player: this.pubkey(),
+        game: this.gameID,
+        playerOneProfile: profileAddress(this.program.programId, gameBefore.players[0]),
+        playerTwoProfile: profileAddress(this.program.programId, gameBefore.players[1]),
+      })
+      .rpc();
+
+    const gameAfter = await this.gameAccount();
+    expect(gameAfter.moves).to.eql(gameBefore.moves.slice(0, -1));
+    expect(gameAfter.state).to.eql(ACTIVE_STATE);
+    expect(gameAfter.takebackRequest).to.be.null;
+  }
+
+  // Offers a rematch, or accepts the opponent's offer
+  public async rematch(): Promise<void> {
+    const gameBefore = await this.gameAccount();
+
+    await this.program.methods
+      .rematch()
+      .accounts({
+        player: this.pubkey(),
+        game: this.gameID,
+      })
+      .rpc();
+
+    const gameAfter = await this.gameAccount();
+    if (gameBefore.rematchOffer === null || gameBefore.rematchOffer.equals(this.pubkey())) {
+      expect(gameAfter.rematchOffer).to.eql(this.pubkey());
+    } else {
+      expect(gameAfter.state).to.eql(ACTIVE_STATE);
+      expect(gameAfter.round).to.equal(gameBefore.round + 1);
+      expect(gameAfter.firstPlayer).to.equal(1 - gameBefore.firstPlayer);
+      expect(gameAfter.moves).to.eql([]);
+    }
+  }
+
+  public async closeGame(): Promise<void> {
+    await this.program.methods
+      .closeGame()
+      .accounts({
+        game: this.gameID,
+        payer: this.pubkey(),
+      })
+      .rpc();
+
+    expect(await this.program.account.game.fetchNullable(this.gameID)).to.be.null;
+  }
+
   public async play(tile: Tile, expectedState: GameState): Promise<void> {
     await this.printBalance('before play');
 
@@ -103,13 +512,20 @@ class Player {
       .accounts({
         player: this.pubkey(),
         game: this.gameID,
+        settlement: {
+          playerOne: gameBefore.players[0],
+          playerTwo: gameBefore.players[1],
+          payer: this.pubkey(),
+        },
       })
       .signers([]) // TODO: Can I just remove this?
       .rpc();
 
+    // Ultimate games address tiles as (sub-board, cell) instead
     const expectedBoard = gameBefore.board;
-    if (row >= 0 && row <= 2 && col >= 0 && col <= 2 && expectedBoard[row][col] === null) {
-      expectedBoard[row][col] = gameBefore.turn % 2 === 1 ? { x: {} } : { o: {} };
+    const size = 'ultimate' in gameBefore.config.mode ? 9 : gameBefore.config.boardSize;
+    if (row < size && col < size && expectedBoard[row * size + col] === null) {
+      expectedBoard[row * size + col] = gameBefore.turn % 2 === 1 ? { x: {} } : { o: {} };
     }
 
     const expectedTurn: number =
@@ -120,9 +536,45 @@ class Player {
     expect(expectedTurn).to.equal(gameAfter.turn);
     expect(expectedBoard).to.eql(gameAfter.board);
     expect(expectedState).to.eql(gameAfter.state);
+    const expectedMoves = [
+      ...gameBefore.moves,
+      { tile, player: ((gameBefore.turn - 1) ^ gameBefore.firstPlayer) % 2, layer: 0 },
+    ];
+    expect(gameAfter.moves).to.eql(expectedMoves);
 
     await this.printBalance('after play');
   }
+
+  public async playCube(tile: CubeTile, expectedState: GameState): Promise<void> {
+    const gameBefore = await this.gameAccount();
+
+    const [layer, row, col] = tile;
+
+    await this.program.methods
+      .playCube([layer, row, col])
+      .accounts({
+        player: this.pubkey(),
+        game: this.gameID,
+        settlement: {
+          playerOne: gameBefore.players[0],
+          playerTwo: gameBefore.players[1],
+          payer: this.pubkey(),
+        },
+      })
+      .rpc();
+
+    const expectedBoard = gameBefore.board;
+    expectedBoard[layer * 16 + row * 4 + col] = gameBefore.turn % 2 === 1 ? { x: {} } : { o: {} };
+
+    const gameAfter = await this.gameAccount();
+    expect(expectedBoard).to.eql(gameAfter.board);
+    expect(expectedState).to.eql(gameAfter.state);
+    expect(gameAfter.moves[gameAfter.moves.length - 1]).to.eql({
+      tile: [row, col],
+      player: ((gameBefore.turn - 1) ^ gameBefore.firstPlayer) % 2,
+      layer,
+    });
+  }
 }
 
 async function transfer(
@@ -141,6 +593,105 @@ async function transfer(
   );
 }
 
+const TOKEN_PROGRAM_ID = anchor.utils.token.TOKEN_PROGRAM_ID;
+const ASSOCIATED_TOKEN_PROGRAM_ID = anchor.utils.token.ASSOCIATED_PROGRAM_ID;
+
+// The few SPL token instructions the token wager test needs, built by hand
+// so the tests get by with the packages that anchor already brings in
+async function createMint(
+  connection: anchor.web3.Connection,
+  payer: anchor.web3.Keypair,
+  authority: anchor.web3.PublicKey,
+  decimals: number,
+): Promise<anchor.web3.PublicKey> {
+  const mint = anchor.web3.Keypair.generate();
+  const space = 82;
+  const initializeMint2 = Buffer.concat([
+    Buffer.from([20, decimals]),
+    authority.toBuffer(),
+    Buffer.from([0]), // no freeze authority
+  ]);
+  await anchor.web3.sendAndConfirmTransaction(
+    connection,
+    new anchor.web3.Transaction().add(
+      anchor.web3.SystemProgram.createAccount({
+        fromPubkey: payer.publicKey,
+        newAccountPubkey: mint.publicKey,
+        lamports: await connection.getMinimumBalanceForRentExemption(space),
+        space,
+        programId: TOKEN_PROGRAM_ID,
+      }),
+      new anchor.web3.TransactionInstruction({
+        programId: TOKEN_PROGRAM_ID,
+        keys: [{ pubkey: mint.publicKey, isSigner: false, isWritable: true }],
+        data: initializeMint2,
+      }),
+    ),
+    [payer, mint],
+  );
+  return mint.publicKey;
+}
+
+function associatedTokenAddress(
+  mint: anchor.web3.PublicKey,
+  owner: anchor.web3.PublicKey,
+): anchor.web3.PublicKey {
+  return anchor.utils.token.associatedAddress({ mint, owner });
+}
+
+async function createAssociatedTokenAccount(
+  connection: anchor.web3.Connection,
+  payer: anchor.web3.Keypair,
+  mint: anchor.web3.PublicKey,
+  owner: anchor.web3.PublicKey,
+): Promise<anchor.web3.PublicKey> {
+  const address = associatedTokenAddress(mint, owner);
+  await anchor.web3.sendAndConfirmTransaction(
+    connection,
+    new anchor.web3.Transaction().add(
+      new anchor.web3.TransactionInstruction({
+        programId: ASSOCIATED_TOKEN_PROGRAM_ID,
+        keys: [
+          { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+          { pubkey: address, isSigner: false, isWritable: true },
+          { pubkey: owner, isSigner: false, isWritable: false },
+          { pubkey: mint, isSigner: false, isWritable: false },
+          { pubkey: anchor.web3.SystemProgram.programId, isSigner: false, isWritable: false },
+          { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        ],
+        data: Buffer.alloc(0),
+      }),
+    ),
+    [payer],
+  );
+  return address;
+}
+
+async function mintTo(
+  connection: anchor.web3.Connection,
+  payer: anchor.web3.Keypair,
+  mint: anchor.web3.PublicKey,
+  destination: anchor.web3.PublicKey,
+  authority: anchor.web3.Keypair,
+  amount: number,
+): Promise<void> {
+  await anchor.web3.sendAndConfirmTransaction(
+    connection,
+    new anchor.web3.Transaction().add(
+      new anchor.web3.TransactionInstruction({
+        programId: TOKEN_PROGRAM_ID,
+        keys: [
+          { pubkey: mint, isSigner: false, isWritable: true },
+          { pubkey: destination, isSigner: false, isWritable: true },
+          { pubkey: authority.publicKey, isSigner: true, isWritable: false },
+        ],
+        data: Buffer.concat([Buffer.from([7]), new anchor.BN(amount).toArrayLike(Buffer, 'le', 8)]),
+      }),
+    ),
+    [payer, authority],
+  );
+}
+
 describe('tic-tac-toe', function () {
   let programOne: anchor.Program<TicTacToe>;
   let programTwo: anchor.Program<TicTacToe>;
@@ -172,14 +723,34 @@ describe('tic-tac-toe', function () {
       playerTwoProvider.publicKey,
       anchor.web3.LAMPORTS_PER_SOL / 10,
     );
+
+    // Games settle with the default K-factor until the config exists
+    const [config] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from('config')],
+      programOne.programId,
+    );
+    if (!(await programOne.account.config.fetchNullable(config))) {
+      await programOne.methods
+        .initializeConfig(32)
+        .accountsPartial({
+          authority: programOne.provider.publicKey,
+          // Only the program's upgrade authority may create the config
+          programData: anchor.web3.PublicKey.findProgramAddressSync(
+            [programOne.programId.toBuffer()],
+            new anchor.web3.PublicKey('BPFLoaderUpgradeab1e11111111111111111111111'),
+          )[0],
+        })
+        .rpc();
+    }
   });
 
   async function startNewGame(
     gameKeyPair: anchor.web3.Keypair = anchor.web3.Keypair.generate(),
+    config: GameConfig = gameConfig(),
   ): Promise<{ playerOne: Player; playerTwo: Player }> {
     const playerOne = new Player(programOne, gameKeyPair.publicKey);
     const playerTwo = new Player(programTwo, gameKeyPair.publicKey);
-    await playerOne.setupGame(gameKeyPair, playerTwo.pubkey());
+    await playerOne.setupGame(gameKeyPair, playerTwo.pubkey(), config);
     return { playerOne, playerTwo };
   }
 
@@ -343,4 +914,1095 @@ describe('tic-tac-toe', function () {
       expect(err.error.errorCode.number).to.equal(6002);
     }
   });
+
+  it('wager must be matched before the first move', async function () {
+    console.log('wager not matched test starting');
+    const { playerOne } = await startNewGame(
+      undefined,
+      gameConfig(anchor.web3.LAMPORTS_PER_SOL / 100),
+    );
+
+    try {
+      await playerOne.play([1, 1], ACTIVE_STATE);
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('WagerNotMatched');
+      expect(err.error.errorCode.number).to.equal(6007);
+    }
+  });
+
+  it('unmatched wager is refunded', async function () {
+    console.log('unmatched wager refund test starting');
+    const wager = anchor.web3.LAMPORTS_PER_SOL / 100;
+    const connection = programOne.provider.connection;
+
+    // Player one calls the game off and gets the wager back with the rent
+    let { playerOne, playerTwo } = await startNewGame(undefined, gameConfig(wager));
+    const pot = await connection.getBalance(playerOne.gameID);
+    const balanceBefore = await connection.getBalance(playerOne.pubkey());
+    await playerOne.closeGame();
+    const balanceAfter = await connection.getBalance(playerOne.pubkey());
+    expect(balanceAfter - balanceBefore).to.equal(pot - 5000);
+
+    // Resigning a game that never started counts for neither player
+    ({ playerOne, playerTwo } = await startNewGame(undefined, gameConfig(wager)));
+    const profilesBefore = [await playerOne.profile(), await playerTwo.profile()];
+    await playerOne.resign({ resigned: { winner: playerTwo.pubkey() } });
+    expect(await playerOne.profile()).to.eql(profilesBefore[0]);
+    expect(await playerTwo.profile()).to.eql(profilesBefore[1]);
+    expect((await playerOne.gameAccount()).ratingChanges).to.eql([0, 0]);
+  });
+
+  it('winner takes the pot', async function () {
+    console.log('winner takes the pot test starting');
+    const wager = anchor.web3.LAMPORTS_PER_SOL / 100;
+    const { playerOne, playerTwo } = await startNewGame(undefined, gameConfig(wager));
+    await playerTwo.matchWager();
+
+    const connection = programOne.provider.connection;
+    const potBalance = await connection.getBalance(playerOne.gameID);
+
+    await playerOne.play([0, 0], ACTIVE_STATE);
+    await playerTwo.play([1, 0], ACTIVE_STATE);
+    await playerOne.play([0, 1], ACTIVE_STATE);
+    await playerTwo.play([1, 1], ACTIVE_STATE);
+
+    const winnerBefore = await connection.getBalance(playerOne.pubkey());
+    await playerOne.play([0, 2], { won: { winner: playerOne.pubkey() } });
+    const winnerAfter = await connection.getBalance(playerOne.pubkey());
+
+    // Only the rent is left in the game account
+    expect(await connection.getBalance(playerOne.gameID)).to.equal(potBalance - 2 * wager);
+    // The winner paid the 5000 lamport transaction fee
+    expect(winnerAfter - winnerBefore).to.equal(2 * wager - 5000);
+  });
+
+  it('close game', async function () {
+    console.log('close game test starting');
+    const { playerOne, playerTwo } = await startNewGame();
+
+    const connection = programOne.provider.connection;
+    const rent = await connection.getBalance(playerOne.gameID);
+
+    await playerOne.play([0, 0], ACTIVE_STATE);
+
+    // The game can't be closed while it is still being played
+    try {
+      await playerOne.closeGame();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('GameNotOver');
+      expect(err.error.errorCode.number).to.equal(6008);
+    }
+
+    await playerTwo.play([1, 0], ACTIVE_STATE);
+    await playerOne.play([0, 1], ACTIVE_STATE);
+    await playerTwo.play([1, 1], ACTIVE_STATE);
+    await playerOne.play([0, 2], { won: { winner: playerOne.pubkey() } });
+
+    const payerBefore = await connection.getBalance(playerOne.pubkey());
+    await playerOne.closeGame();
+    const payerAfter = await connection.getBalance(playerOne.pubkey());
+    expect(payerAfter - payerBefore).to.equal(rent - 5000);
+  });
+
+  it('player two resigns!', async function () {
+    console.log('player two resigns test starting');
+    const wager = anchor.web3.LAMPORTS_PER_SOL / 100;
+    const { playerOne, playerTwo } = await startNewGame(undefined, gameConfig(wager));
+    await playerTwo.matchWager();
+
+    await playerOne.play([1, 1], ACTIVE_STATE);
+
+    const connection = programOne.provider.connection;
+    const winnerBefore = await connection.getBalance(playerOne.pubkey());
+    await playerTwo.resign({ resigned: { winner: playerOne.pubkey() } });
+    const winnerAfter = await connection.getBalance(playerOne.pubkey());
+    expect(winnerAfter - winnerBefore).to.equal(2 * wager);
+
+    // No more moves after resigning
+    try {
+      await playerTwo.play([0, 0], ACTIVE_STATE);
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('GameAlreadyOver');
+      expect(err.error.errorCode.number).to.equal(6002);
+    }
+  });
+
+  it('draw by agreement', async function () {
+    console.log('draw by agreement test starting');
+    const { playerOne, playerTwo } = await startNewGame();
+
+    await playerOne.play([1, 1], ACTIVE_STATE);
+
+    // Nothing to accept yet
+    try {
+      await playerTwo.acceptDraw();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('NoDrawOffer');
+      expect(err.error.errorCode.number).to.equal(6016);
+    }
+
+    // Players can't accept their own offer
+    await playerOne.offerDraw();
+    try {
+      await playerOne.acceptDraw();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('CannotAcceptOwnDrawOffer');
+      expect(err.error.errorCode.number).to.equal(6017);
+    }
+
+    // The next move withdraws the offer
+    await playerTwo.play([0, 0], ACTIVE_STATE);
+    try {
+      await playerTwo.acceptDraw();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('NoDrawOffer');
+      expect(err.error.errorCode.number).to.equal(6016);
+    }
+
+    await playerTwo.offerDraw();
+    await playerOne.acceptDraw();
+  });
+
+  it('takeback', async function () {
+    console.log('takeback test starting');
+    const { playerOne, playerTwo } = await startNewGame();
+
+    await playerOne.play([0, 0], ACTIVE_STATE);
+    await playerTwo.play([1, 1], ACTIVE_STATE);
+
+    // Only the player who made the last move can ask for it back
+    try {
+      await playerOne.requestTakeback();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('NothingToTakeBack');
+      expect(err.error.errorCode.number).to.equal(6038);
+    }
+
+    await playerTwo.requestTakeback();
+    try {
+      await playerTwo.acceptTakeback();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('CannotAcceptOwnTakeback');
+      expect(err.error.errorCode.number).to.equal(6040);
+    }
+    await playerOne.acceptTakeback();
+
+    // Taking back the winning move reopens the game and undoes the result
+    await playerTwo.play([1, 0], ACTIVE_STATE);
+    await playerOne.play([0, 1], ACTIVE_STATE);
+    await playerTwo.play([2, 0], ACTIVE_STATE);
+    const profileBefore = await playerOne.profile();
+    await playerOne.play([0, 2], { won: { winner: playerOne.pubkey() } });
+    await playerOne.requestTakeback();
+    await playerTwo.acceptTakeback();
+    expect(await playerOne.profile()).to.eql(profileBefore);
+  });
+
+  it('rematch', async function () {
+    console.log('rematch test starting');
+    const { playerOne, playerTwo } = await startNewGame();
+
+    // No rematch before the game is over
+    try {
+      await playerOne.rematch();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('GameNotOver');
+      expect(err.error.errorCode.number).to.equal(6008);
+    }
+
+    await playerOne.play([1, 1], ACTIVE_STATE);
+    await playerTwo.resign({ resigned: { winner: playerOne.pubkey() } });
+    await playerTwo.rematch();
+    await playerOne.rematch();
+
+    // Player two opens the second round as X
+    await playerTwo.play([1, 1], ACTIVE_STATE);
+    await playerOne.play([0, 0], ACTIVE_STATE);
+    expect((await playerOne.gameAccount()).board[4]).to.eql({ x: {} });
+  });
+
+  it('claim timeout', async function () {
+    console.log('claim timeout test starting');
+    const { playerOne, playerTwo } = await startNewGame(undefined, gameConfig(0, 2));
+
+    await playerOne.play([1, 1], ACTIVE_STATE);
+
+    // Player one is waiting on player two, but the timeout hasn't passed yet
+    try {
+      await playerOne.claimTimeout();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('TimeoutNotReached');
+      expect(err.error.errorCode.number).to.equal(6012);
+    }
+
+    // Player two can't claim a timeout on their own turn
+    try {
+      await playerTwo.claimTimeout();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('NotPlayersTurn');
+      expect(err.error.errorCode.number).to.equal(6003);
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+    await playerOne.claimTimeout();
+  });
+
+  it('open challenge', async function () {
+    console.log('open challenge test starting');
+    const gameKeyPair = anchor.web3.Keypair.generate();
+    const playerOne = new Player(programOne, gameKeyPair.publicKey);
+    const playerTwo = new Player(programTwo, gameKeyPair.publicKey);
+    await playerOne.setupGame(gameKeyPair, null);
+
+    // Nobody can move until the second seat is filled
+    try {
+      await playerOne.play([1, 1], ACTIVE_STATE);
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('WaitingForOpponent');
+      expect(err.error.errorCode.number).to.equal(6013);
+    }
+
+    try {
+      await playerOne.joinGame();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('CannotJoinOwnGame');
+      expect(err.error.errorCode.number).to.equal(6014);
+    }
+
+    await playerTwo.joinGame();
+
+    await playerOne.play([0, 0], ACTIVE_STATE);
+    await playerTwo.play([1, 0], ACTIVE_STATE);
+    await playerOne.play([0, 1], ACTIVE_STATE);
+    await playerTwo.play([1, 1], ACTIVE_STATE);
+    await playerOne.play([0, 2], { won: { winner: playerOne.pubkey() } });
+  });
+
+  it('pda game', async function () {
+    console.log('pda game test starting');
+    const nonce = Date.now();
+    const gameID = gamePda(
+      programOne.programId,
+      programOne.provider.publicKey,
+      programTwo.provider.publicKey,
+      nonce,
+    );
+    const playerOne = new Player(programOne, gameID);
+    const playerTwo = new Player(programTwo, gameID);
+    await playerOne.setupPdaGame(playerTwo.pubkey(), nonce);
+
+    await playerOne.play([0, 0], ACTIVE_STATE);
+    await playerTwo.play([1, 0], ACTIVE_STATE);
+    await playerOne.play([0, 1], ACTIVE_STATE);
+    await playerTwo.play([1, 1], ACTIVE_STATE);
+    await playerOne.play([0, 2], { won: { winner: playerOne.pubkey() } });
+  });
+
+  it('best of three series', async function () {
+    console.log('best of three series test starting');
+    const seriesKeypair = anchor.web3.Keypair.generate();
+    const series = seriesKeypair.publicKey;
+    await programOne.methods
+      .createSeries(programTwo.provider.publicKey, 3, gameConfig())
+      .accounts({ series, playerOne: programOne.provider.publicKey })
+      .signers([seriesKeypair])
+      .rpc();
+
+    // X takes the top row every game, and X alternates between the players
+    const programs = [programOne, programTwo];
+    for (let index = 0; index < 3; index++) {
+      const gameID = seriesGame(programOne.programId, series, index);
+      const x = new Player(programs[index % 2], gameID);
+      const o = new Player(programs[(index + 1) % 2], gameID);
+
+      // Only the player due to play X starts the game
+      if (index === 0) {
+        try {
+          await o.program.methods
+            .startNextGame()
+            .accountsPartial({ series, game: gameID, playerOne: o.pubkey() })
+            .rpc();
+          chai.assert(false, "should've failed but didn't");
+        } catch (_err) {
+          expect(_err).to.be.instanceOf(anchor.AnchorError);
+          const err: anchor.AnchorError = _err;
+          expect(err.error.errorCode.code).to.equal('NotPlayersTurn');
+          expect(err.error.errorCode.number).to.equal(6003);
+        }
+      }
+      await x.program.methods
+        .startNextGame()
+        .accountsPartial({ series, game: gameID, playerOne: x.pubkey() })
+        .rpc();
+
+      await x.play([0, 0], ACTIVE_STATE);
+      await o.play([1, 0], ACTIVE_STATE);
+      await x.play([0, 1], ACTIVE_STATE);
+      await o.play([1, 1], ACTIVE_STATE);
+      await x.play([0, 2], { won: { winner: x.pubkey() } });
+
+      // The game stays around until the series has counted it
+      if (index === 0) {
+        expect((await x.gameAccount()).owner).to.eql(series);
+        try {
+          await x.closeGame();
+          chai.assert(false, "should've failed but didn't");
+        } catch (_err) {
+          expect(_err).to.be.instanceOf(anchor.AnchorError);
+          const err: anchor.AnchorError = _err;
+          expect(err.error.errorCode.code).to.equal('GameNotScored');
+          expect(err.error.errorCode.number).to.equal(6059);
+        }
+      }
+      await programOne.methods.updateSeries().accounts({ series, game: gameID }).rpc();
+      if (index === 0) {
+        await x.closeGame();
+      }
+    }
+
+    const seriesAccount = await programOne.account.series.fetch(series);
+    expect(seriesAccount.wins).to.eql([2, 1]);
+    expect(seriesAccount.winner).to.eql(programOne.provider.publicKey);
+
+    try {
+      await programOne.methods
+        .startNextGame()
+        .accountsPartial({
+          series,
+          game: seriesGame(programOne.programId, series, 3),
+          playerOne: programOne.provider.publicKey,
+        })
+        .rpc();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('SeriesOver');
+      expect(err.error.errorCode.number).to.equal(6044);
+    }
+  });
+
+  it('series forfeit', async function () {
+    console.log('series forfeit test starting');
+    const seriesKeypair = anchor.web3.Keypair.generate();
+    const series = seriesKeypair.publicKey;
+    await programOne.methods
+      .createSeries(programTwo.provider.publicKey, 1, gameConfig(0, 2))
+      .accounts({ series, playerOne: programOne.provider.publicKey })
+      .signers([seriesKeypair])
+      .rpc();
+
+    // Player one is due to play X but doesn't start the game
+    const claimForfeit = () =>
+      programTwo.methods
+        .claimSeriesForfeit()
+        .accounts({ series, player: programTwo.provider.publicKey })
+        .rpc();
+    try {
+      await claimForfeit();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('TimeoutNotReached');
+    }
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+    await claimForfeit();
+
+    const seriesAccount = await programOne.account.series.fetch(series);
+    expect(seriesAccount.gamesPlayed).to.equal(1);
+    expect(seriesAccount.wins).to.eql([0, 1]);
+    expect(seriesAccount.winner).to.eql(programTwo.provider.publicKey);
+  });
+
+  it('tournament registration', async function () {
+    console.log('tournament registration test starting');
+    const tournamentKeypair = anchor.web3.Keypair.generate();
+    const tournament = tournamentKeypair.publicKey;
+
+    // Brackets hold 8, 16 or 32 players
+    try {
+      await programOne.methods
+        .createTournament(6, gameConfig())
+        .accounts({ tournament, authority: programOne.provider.publicKey })
+        .signers([tournamentKeypair])
+        .rpc();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('InvalidTournamentSize');
+      expect(err.error.errorCode.number).to.equal(6048);
+    }
+    await programOne.methods
+      .createTournament(8, gameConfig())
+      .accounts({ tournament, authority: programOne.provider.publicKey })
+      .signers([tournamentKeypair])
+      .rpc();
+
+    await programOne.methods
+      .joinTournament()
+      .accounts({ tournament, player: programOne.provider.publicKey })
+      .rpc();
+    try {
+      await programOne.methods
+        .joinTournament()
+        .accounts({ tournament, player: programOne.provider.publicKey })
+        .rpc();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('AlreadyRegistered');
+      expect(err.error.errorCode.number).to.equal(6051);
+    }
+
+    // Matches only start once the bracket is full
+    try {
+      await programOne.methods
+        .startMatch(0)
+        .accountsPartial({
+          tournament,
+          game: anchor.web3.PublicKey.findProgramAddressSync(
+            [Buffer.from('game'), tournament.toBuffer(), Buffer.from([0]), Buffer.from([0])],
+            programOne.programId,
+          )[0],
+          payer: programOne.provider.publicKey,
+        })
+        .rpc();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('TournamentNotStarted');
+      expect(err.error.errorCode.number).to.equal(6052);
+    }
+
+    const tournamentAccount = await programOne.account.tournament.fetch(tournament);
+    expect(tournamentAccount.entrants.map((entrant) => entrant.player)).to.eql([
+      programOne.provider.publicKey,
+    ]);
+    expect(tournamentAccount.round).to.equal(0);
+  });
+
+  it('matchmaking queue', async function () {
+    console.log('matchmaking queue test starting');
+    const connection = programOne.provider.connection;
+    const id = Date.now();
+    const queue = queuePda(programOne.programId, id);
+    const wager = anchor.web3.LAMPORTS_PER_SOL / 1000;
+    await programOne.methods
+      .createQueue(new anchor.BN(id), gameConfig(wager))
+      .accountsPartial({ queue, payer: programOne.provider.publicKey })
+      .rpc();
+    const rent = await connection.getBalance(queue);
+
+    // Leaving the queue refunds the deposit
+    await programOne.methods
+      .enqueue(100)
+      .accounts({ queue, player: programOne.provider.publicKey })
+      .rpc();
+    expect(await connection.getBalance(queue)).to.equal(rent + wager);
+    try {
+      await programOne.methods
+        .enqueue(null)
+        .accounts({ queue, player: programOne.provider.publicKey })
+        .rpc();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('AlreadyQueued');
+      expect(err.error.errorCode.number).to.equal(6055);
+    }
+    await programOne.methods
+      .dequeue()
+      .accounts({ queue, player: programOne.provider.publicKey })
+      .rpc();
+    expect(await connection.getBalance(queue)).to.equal(rent);
+    try {
+      await programOne.methods
+        .dequeue()
+        .accounts({ queue, player: programOne.provider.publicKey })
+        .rpc();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('NotQueued');
+      expect(err.error.errorCode.number).to.equal(6057);
+    }
+
+    // Nobody to play against yet
+    await programOne.methods
+      .enqueue(100)
+      .accounts({ queue, player: programOne.provider.publicKey })
+      .rpc();
+    const gameID = queueGame(programOne.programId, queue, 0);
+    try {
+      await programOne.methods
+        .pairPlayers()
+        .accountsPartial({ queue, game: gameID, payer: programOne.provider.publicKey })
+        .rpc();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('NoMatchInQueue');
+      expect(err.error.errorCode.number).to.equal(6058);
+    }
+
+    // Player one has waited longer and plays X, with both deposits in the pot
+    await programTwo.methods
+      .enqueue(null)
+      .accounts({ queue, player: programTwo.provider.publicKey })
+      .rpc();
+    await programOne.methods
+      .pairPlayers()
+      .accountsPartial({ queue, game: gameID, payer: programOne.provider.publicKey })
+      .rpc();
+    expect(await connection.getBalance(queue)).to.equal(rent);
+    const queueAccount = await programOne.account.queue.fetch(queue);
+    expect(queueAccount.entries).to.eql([]);
+    expect(queueAccount.gamesCreated.toNumber()).to.equal(1);
+
+    const playerOne = new Player(programOne, gameID);
+    const playerTwo = new Player(programTwo, gameID);
+    const gameAccount = await playerOne.gameAccount();
+    expect(gameAccount.players).to.eql([playerOne.pubkey(), playerTwo.pubkey()]);
+    expect(gameAccount.wagerMatched).to.equal(true);
+
+    await playerOne.play([0, 0], ACTIVE_STATE);
+    await playerTwo.play([1, 0], ACTIVE_STATE);
+    await playerOne.play([0, 1], ACTIVE_STATE);
+    await playerTwo.play([1, 1], ACTIVE_STATE);
+    await playerOne.play([0, 2], { won: { winner: playerOne.pubkey() } });
+  });
+
+  it('player profiles', async function () {
+    console.log('player profiles test starting');
+    const { playerOne, playerTwo } = await startNewGame();
+
+    const noProfile = { wins: 0, losses: 0, ties: 0 };
+    const playerOneBefore = (await playerOne.profile()) ?? noProfile;
+    const playerTwoBefore = (await playerTwo.profile()) ?? noProfile;
+
+    await playerOne.play([0, 0], ACTIVE_STATE);
+    await playerTwo.play([1, 1], ACTIVE_STATE);
+    await playerOne.play([2, 0], ACTIVE_STATE);
+    await playerTwo.play([1, 0], ACTIVE_STATE);
+    await playerOne.play([1, 2], ACTIVE_STATE);
+    await playerTwo.play([0, 1], ACTIVE_STATE);
+    await playerOne.play([2, 1], ACTIVE_STATE);
+    await playerTwo.play([2, 2], ACTIVE_STATE);
+    await playerOne.play([0, 2], TIE_STATE);
+
+    const playerOneAfter = await playerOne.profile();
+    const playerTwoAfter = await playerTwo.profile();
+    expect(playerOneAfter.player).to.eql(playerOne.pubkey());
+    expect(playerOneAfter.ties).to.equal(playerOneBefore.ties + 1);
+    expect(playerOneAfter.wins).to.equal(playerOneBefore.wins);
+    expect(playerTwoAfter.ties).to.equal(playerTwoBefore.ties + 1);
+    expect(playerTwoAfter.losses).to.equal(playerTwoBefore.losses);
+
+    // Resigning counts as a loss
+    const { playerTwo: resigner } = await startNewGame();
+    await resigner.resign({ resigned: { winner: playerOne.pubkey() } });
+    expect((await playerOne.profile()).wins).to.equal(playerOneAfter.wins + 1);
+    expect((await resigner.profile()).losses).to.equal(playerTwoAfter.losses + 1);
+  });
+
+  it('elo ratings', async function () {
+    console.log('elo ratings test starting');
+    const { playerOne, playerTwo } = await startNewGame();
+
+    // Make sure both players have a rating before the game we measure
+    const { playerOne: warmUpOne, playerTwo: warmUpTwo } = await startNewGame();
+    await warmUpOne.play([0, 0], ACTIVE_STATE);
+    await warmUpTwo.resign({ resigned: { winner: warmUpOne.pubkey() } });
+
+    const playerOneBefore = await playerOne.profile();
+    const playerTwoBefore = await playerTwo.profile();
+
+    await playerOne.play([0, 0], ACTIVE_STATE);
+    await playerTwo.play([1, 0], ACTIVE_STATE);
+    await playerOne.play([0, 1], ACTIVE_STATE);
+    await playerTwo.play([1, 1], ACTIVE_STATE);
+    await playerOne.play([0, 2], { won: { winner: playerOne.pubkey() } });
+
+    const [playerOneChange, playerTwoChange] = (await playerOne.gameAccount()).ratingChanges;
+    expect(playerOneChange).to.be.greaterThan(0);
+    expect(playerTwoChange).to.be.lessThan(0);
+    expect((await playerOne.profile()).rating).to.equal(playerOneBefore.rating + playerOneChange);
+    expect((await playerTwo.profile()).rating).to.equal(playerTwoBefore.rating + playerTwoChange);
+  });
+
+  it('game lifecycle events', async function () {
+    console.log('game lifecycle events test starting');
+    const events: { name: string; event: any }[] = [];
+    const listeners = ['gameStarted', 'movePlayed', 'gameEnded'].map((name) =>
+      programOne.addEventListener(name as any, (event) => events.push({ name, event })),
+    );
+
+    const { playerOne, playerTwo } = await startNewGame();
+    await playerOne.play([0, 0], ACTIVE_STATE);
+    await playerTwo.resign({ resigned: { winner: playerOne.pubkey() } });
+
+    // Give the websocket subscription a moment to deliver the last event
+    await new Promise((resolve) => setTimeout(resolve, 1000));
+    for (const listener of listeners) {
+      await programOne.removeEventListener(listener);
+    }
+
+    expect(events.map((e) => e.name)).to.eql(['gameStarted', 'movePlayed', 'gameEnded']);
+    const [started, moved, ended] = events.map((e) => e.event);
+    expect(started.game).to.eql(playerOne.gameID);
+    expect(started.players).to.eql([playerOne.pubkey(), playerTwo.pubkey()]);
+    expect(moved.player).to.eql(playerOne.pubkey());
+    expect(moved.turn).to.equal(1);
+    expect(ended.outcome).to.eql({ resigned: { winner: playerOne.pubkey() } });
+  });
+
+  it('token wager', async function () {
+    console.log('token wager test starting');
+    const connection = programOne.provider.connection;
+    const payer = ((programOne.provider as AnchorProvider).wallet as Wallet).payer;
+
+    // A local mint standing in for the community token
+    const mint = await createMint(connection, payer, payer.publicKey, 0);
+    const playerOneTokens = await createAssociatedTokenAccount(
+      connection,
+      payer,
+      mint,
+      programOne.provider.publicKey,
+    );
+    const playerTwoTokens = await createAssociatedTokenAccount(
+      connection,
+      payer,
+      mint,
+      programTwo.provider.publicKey,
+    );
+    await mintTo(connection, payer, mint, playerOneTokens, payer, 1000);
+    await mintTo(connection, payer, mint, playerTwoTokens, payer, 1000);
+    const tokenBalance = async (account: anchor.web3.PublicKey) =>
+      Number((await connection.getTokenAccountBalance(account)).value.amount);
+
+    const wager = 100;
+    const gameKeyPair = anchor.web3.Keypair.generate();
+    const playerOne = new Player(programOne, gameKeyPair.publicKey);
+    const playerTwo = new Player(programTwo, gameKeyPair.publicKey);
+    const [escrowAuthority] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from('escrow'), gameKeyPair.publicKey.toBuffer()],
+      programOne.programId,
+    );
+    const escrow = associatedTokenAddress(mint, escrowAuthority);
+
+    await programOne.methods
+      .setupGame(playerTwo.pubkey(), gameConfig(wager))
+      .accounts({
+        game: gameKeyPair.publicKey,
+        playerOne: playerOne.pubkey(),
+        tokenWager: {
+          mint,
+          playerTokenAccount: playerOneTokens,
+          escrow,
+          escrowAuthority,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        },
+      })
+      .signers([gameKeyPair])
+      .rpc();
+    expect((await playerOne.gameAccount()).mint).to.eql(mint);
+    expect(await tokenBalance(escrow)).to.equal(wager);
+
+    await programTwo.methods
+      .matchWager()
+      .accounts({
+        game: gameKeyPair.publicKey,
+        playerTwo: playerTwo.pubkey(),
+        tokenWager: {
+          mint,
+          playerTokenAccount: playerTwoTokens,
+          escrow,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      })
+      .rpc();
+    expect(await tokenBalance(escrow)).to.equal(2 * wager);
+
+    // Ending the game without the token accounts can't pay out the pot
+    await playerOne.play([1, 1], ACTIVE_STATE);
+    try {
+      await playerTwo.resign({ resigned: { winner: playerOne.pubkey() } });
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('MissingTokenAccounts');
+      expect(err.error.errorCode.number).to.equal(6019);
+    }
+
+    await programTwo.methods
+      .resign()
+      .accounts({
+        game: gameKeyPair.publicKey,
+        player: playerTwo.pubkey(),
+        settlement: {
+          playerOne: playerOne.pubkey(),
+          playerTwo: playerTwo.pubkey(),
+          payer: playerTwo.pubkey(),
+          mint,
+          escrow,
+          escrowAuthority,
+          playerOneTokenAccount: playerOneTokens,
+          playerTwoTokenAccount: playerTwoTokens,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      })
+      .rpc();
+    expect(await tokenBalance(playerOneTokens)).to.equal(1000 + wager);
+    expect(await tokenBalance(playerTwoTokens)).to.equal(1000 - wager);
+    expect(await tokenBalance(escrow)).to.equal(0);
+
+    // Closing the game also closes the emptied escrow
+    await programOne.methods
+      .closeGame()
+      .accounts({
+        game: gameKeyPair.publicKey,
+        payer: playerOne.pubkey(),
+        escrow,
+        escrowAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    expect(await connection.getAccountInfo(escrow)).to.be.null;
+  });
+
+  it('larger board', async function () {
+    console.log('larger board test starting');
+    const { playerOne, playerTwo } = await startNewGame(undefined, gameConfig(0, 0, 15, 5));
+
+    // Tiles past the classic 3x3 board are in bounds now
+    await playerOne.play([14, 14], ACTIVE_STATE);
+    await playerTwo.play([0, 0], ACTIVE_STATE);
+    for (let col = 0; col < 3; col++) {
+      await playerOne.play([7, 10 + col], ACTIVE_STATE);
+      await playerTwo.play([1, col], ACTIVE_STATE);
+    }
+    await playerOne.play([7, 13], ACTIVE_STATE);
+    await playerTwo.play([2, 0], ACTIVE_STATE);
+    await playerOne.play([7, 14], { won: { winner: playerOne.pubkey() } });
+
+    try {
+      await startNewGame(undefined, gameConfig(0, 0, 16, 5));
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('InvalidBoardSize');
+      expect(err.error.errorCode.number).to.equal(6022);
+    }
+
+    try {
+      await startNewGame(undefined, gameConfig(0, 0, 4, 5));
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('InvalidWinLength');
+      expect(err.error.errorCode.number).to.equal(6023);
+    }
+  });
+
+  it('ultimate', async function () {
+    console.log('ultimate test starting');
+    const { playerOne, playerTwo } = await startNewGame(
+      undefined,
+      gameConfig(0, 0, 3, 3, { ultimate: {} }),
+    );
+
+    await playerOne.play([4, 0], ACTIVE_STATE);
+    // Cell 0 sends player two to sub-board 0
+    try {
+      await playerTwo.play([1, 0], ACTIVE_STATE);
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('WrongSubBoard');
+      expect(err.error.errorCode.number).to.equal(6024);
+    }
+    await playerTwo.resign({ resigned: { winner: playerOne.pubkey() } });
+
+    // Player one takes the middle rows of the top three sub-boards, while
+    // player two keeps sending them back there
+    const { playerOne: one, playerTwo: two } = await startNewGame(
+      undefined,
+      gameConfig(0, 0, 3, 3, { ultimate: {} }),
+    );
+    // prettier-ignore
+    const tiles: Tile[] = [
+      [0, 3], [3, 1], [1, 4], [4, 2], [2, 5], [5, 0], [0, 4], [4, 1],
+      [1, 5], [5, 2], [2, 3], [3, 0], [0, 5], [5, 1], [1, 3], [3, 2],
+    ];
+    for (const [i, tile] of tiles.entries()) {
+      await (i % 2 === 0 ? one : two).play(tile, ACTIVE_STATE);
+    }
+    await one.play([2, 4], { won: { winner: one.pubkey() } });
+
+    const X = { x: {} };
+    const O = { o: {} };
+    expect((await one.gameAccount()).metaBoard).to.eql([X, X, X, O, null, O, null, null, null]);
+  });
+
+  it('cube', async function () {
+    const { playerOne, playerTwo } = await startNewGame(
+      undefined,
+      gameConfig(0, 0, 4, 4, { cube: {} }),
+    );
+
+    // Cube games only take cube tiles
+    try {
+      await playerOne.play([0, 0], ACTIVE_STATE);
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('WrongGameMode');
+      expect(err.error.errorCode.number).to.equal(6026);
+    }
+
+    // Player one wins along the diagonal from corner to corner of the cube
+    await playerOne.playCube([0, 0, 0], ACTIVE_STATE);
+    await playerTwo.playCube([0, 0, 1], ACTIVE_STATE);
+    await playerOne.playCube([1, 1, 1], ACTIVE_STATE);
+    await playerTwo.playCube([0, 0, 2], ACTIVE_STATE);
+    await playerOne.playCube([2, 2, 2], ACTIVE_STATE);
+    await playerTwo.playCube([0, 0, 3], ACTIVE_STATE);
+    await playerOne.playCube([3, 3, 3], { won: { winner: playerOne.pubkey() } });
+  });
+
+  it('misère', async function () {
+    const { playerOne, playerTwo } = await startNewGame(
+      undefined,
+      gameConfig(0, 0, 3, 3, { classic: {} }, { misere: {} }),
+    );
+
+    // Player one completes the top row and loses
+    await playerOne.play([0, 0], ACTIVE_STATE);
+    await playerTwo.play([1, 0], ACTIVE_STATE);
+    await playerOne.play([0, 1], ACTIVE_STATE);
+    await playerTwo.play([1, 1], ACTIVE_STATE);
+    await playerOne.play([0, 2], { won: { winner: playerTwo.pubkey() } });
+  });
+
+  it('random first player', async function () {
+    // Not revealing has to forfeit on time
+    try {
+      await startNewGame(
+        undefined,
+        gameConfig(0, 0, 3, 3, { classic: {} }, { normal: {} }, { random: {} }),
+      );
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('NoMoveTimeout');
+      expect(err.error.errorCode.number).to.equal(6011);
+    }
+
+    const { playerOne, playerTwo } = await startNewGame(
+      undefined,
+      gameConfig(0, 60, 3, 3, { classic: {} }, { normal: {} }, { random: {} }),
+    );
+    const secretOne = Buffer.alloc(32, 1);
+    const secretTwo = Buffer.alloc(32, 2);
+
+    await playerOne.commitSecret(secretOne);
+    try {
+      await playerOne.revealSecret(secretOne);
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('WaitingForCommitment');
+      expect(err.error.errorCode.number).to.equal(6029);
+    }
+    await playerTwo.commitSecret(secretTwo);
+    await playerOne.revealSecret(secretOne);
+    try {
+      await playerOne.play([0, 0], ACTIVE_STATE);
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('FirstPlayerNotDecided');
+      expect(err.error.errorCode.number).to.equal(6032);
+    }
+    await playerTwo.revealSecret(secretTwo);
+
+    // 1 XOR 2 is odd, so player two plays X
+    expect((await playerOne.gameAccount()).firstPlayer).to.equal(1);
+    await playerTwo.play([0, 0], ACTIVE_STATE);
+    await playerOne.play([1, 0], ACTIVE_STATE);
+    await playerTwo.play([0, 1], ACTIVE_STATE);
+    await playerOne.play([1, 1], ACTIVE_STATE);
+    await playerTwo.play([0, 2], { won: { winner: playerTwo.pubkey() } });
+  });
+
+  it('chess clock', async function () {
+    const { playerOne, playerTwo } = await startNewGame(
+      undefined,
+      gameConfig(0, 0, 3, 3, { classic: {} }, { normal: {} }, { playerOne: {} }, 2, 1),
+    );
+
+    await playerOne.play([1, 1], ACTIVE_STATE);
+    await playerTwo.play([0, 0], ACTIVE_STATE);
+
+    // Player one's clock runs out, after which they can't move but can be flagged
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+    try {
+      await playerOne.play([2, 2], ACTIVE_STATE);
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('OutOfTime');
+      expect(err.error.errorCode.number).to.equal(6035);
+    }
+    await playerTwo.flag();
+    expect((await playerTwo.gameAccount()).state).to.eql({
+      timedOut: { winner: playerTwo.pubkey() },
+    });
+  });
+
+  it('migrate game', async function () {
+    const { playerOne } = await startNewGame();
+
+    // A game set up by this version of the program has nothing to migrate
+    try {
+      await programOne.methods
+        .migrateGame()
+        .accounts({ game: playerOne.gameID, payer: playerOne.pubkey() })
+        .rpc();
+      chai.assert(false, "should've failed but didn't");
+    } catch (_err) {
+      expect(_err).to.be.instanceOf(anchor.AnchorError);
+      const err: anchor.AnchorError = _err;
+      expect(err.error.errorCode.code).to.equal('AlreadyMigrated');
+      expect(err.error.errorCode.number).to.equal(6036);
+    }
+
+    // A game in the original layout, loaded into the validator from
+    // tests/fixtures, with X on two corners and O in the middle
+    const original = new anchor.web3.PublicKey('3y5DnjAG2gzQFTpkw22Uk9QaPQKWNrD8DEg936xpvCdu');
+    await programOne.methods
+      .migrateGame()
+      .accounts({ game: original, payer: playerOne.pubkey() })
+      .rpc();
+    const migrated = (await programOne.account.game.fetch(original)) as GameAccount;
+    const [x, o] = [{ x: {} }, { o: {} }];
+    expect(migrated.version).to.equal(GAME_VERSION);
+    expect(migrated.turn).to.equal(4);
+    expect(migrated.board).to.eql([x, null, null, null, o, null, null, null, x]);
+    expect(migrated.state).to.eql(ACTIVE_STATE);
+    expect(migrated.config).to.eql(gameConfig());
+    expect(migrated.payer).to.eql(migrated.players[0]);
+    expect(migrated.moves).to.eql([]);
+  });
+
+  // The program's first reply searches up to 640 positions, which can take
+  // more than the 200k compute units an instruction gets by default, so
+  // moves against the program ask for more
+  const PROGRAM_REPLY_COMPUTE_UNITS = 400_000;
+
+  it('against the program', async function () {
+    const gameKeyPair = anchor.web3.Keypair.generate();
+    const player = new Player(programOne, gameKeyPair.publicKey);
+    const program = programPlayer(programOne.programId);
+    await player.setupGame(gameKeyPair, program);
+
+    // Player one takes the first empty tile every turn, the program replies
+    // in the same instruction and never loses
+    let game = await player.gameAccount();
+    const computeUnits: number[] = [];
+    while ('active' in game.state) {
+      const index = game.board.findIndex((tile) => tile === null);
+      const signature = await programOne.methods
+        .play([Math.floor(index / 3), index % 3])
+        .accounts({
+          player: player.pubkey(),
+          game: player.gameID,
+          settlement: {
+            playerOne: player.pubkey(),
+            playerTwo: program,
+            payer: player.pubkey(),
+          },
+        })
+        .preInstructions([
+          anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({
+            units: PROGRAM_REPLY_COMPUTE_UNITS,
+          }),
+        ])
+        .rpc({ commitment: 'confirmed' });
+      const transaction = await programOne.provider.connection.getTransaction(signature, {
+        commitment: 'confirmed',
+        maxSupportedTransactionVersion: 0,
+      });
+      computeUnits.push(transaction.meta.computeUnitsConsumed);
+
+      const before = game.moves.length;
+      game = await player.gameAccount();
+      if ('active' in game.state) {
+        expect(game.moves.length).to.equal(before + 2);
+        expect(game.moves[before + 1].player).to.equal(1);
+      }
+    }
+    expect(game.state).to.not.eql({ won: { winner: player.pubkey() } });
+
+    // A quarter of the requested limit is kept free, so a search that grows
+    // fails here before it runs out of compute units for players
+    console.log('compute units per move against the program:', computeUnits);
+    expect(Math.max(...computeUnits)).to.be.lessThan((PROGRAM_REPLY_COMPUTE_UNITS * 3) / 4);
+  });
 });
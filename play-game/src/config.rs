@@ -0,0 +1,60 @@
+use borsh::BorshSerialize;
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction},
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::Transaction,
+};
+
+use crate::{transaction::send_transaction_and_print_logs, util::get_anchor_discriminator};
+
+/// Same as the K-factor games settle with before the config exists
+pub const DEFAULT_K_FACTOR: u8 = 32;
+
+pub fn config_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], program_id).0
+}
+
+/// Creates the program's config, with `authority` allowed to update it
+/// later, unless a previous run already did. Only the program's upgrade
+/// authority can create it.
+pub fn initialize_config_if_needed(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    authority: &Keypair,
+    k_factor: u8,
+) {
+    let config = config_address(program_id);
+    if rpc_client.get_account(&config).is_ok() {
+        return;
+    }
+
+    let mut instruction_data = get_anchor_discriminator("global:initialize_config").to_vec();
+    k_factor.serialize(&mut instruction_data).unwrap();
+
+    let instruction = Instruction::new_with_bytes(
+        *program_id,
+        &instruction_data,
+        vec![
+            AccountMeta::new(config, false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(
+                bpf_loader_upgradeable::get_program_data_address(program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    );
+
+    let recent_block_hash = rpc_client.get_latest_blockhash().unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&authority.pubkey()),
+        &[authority],
+        recent_block_hash,
+    );
+    send_transaction_and_print_logs(rpc_client, &transaction).unwrap();
+}
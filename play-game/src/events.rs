@@ -0,0 +1,94 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use borsh::BorshDeserialize;
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    game::{GameState, Tile},
+    util::get_anchor_discriminator,
+};
+
+/// Prefix of the log line Anchor's `emit!` writes an event to
+const PROGRAM_DATA: &str = "Program data: ";
+
+#[derive(BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct GameStarted {
+    pub game: Pubkey,
+    pub players: [Pubkey; 2],
+    pub wager: u64,
+    pub move_timeout: i64,
+    pub mint: Option<Pubkey>,
+}
+
+#[derive(BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct MovePlayed {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub tile: Tile,
+    pub layer: u8,
+    pub turn: u8,
+}
+
+#[derive(BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct GameEnded {
+    pub game: Pubkey,
+    pub outcome: GameState,
+    pub payouts: [u64; 2],
+    pub rating_changes: [i32; 2],
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Event {
+    GameStarted(GameStarted),
+    MovePlayed(MovePlayed),
+    GameEnded(GameEnded),
+}
+
+/// Decodes a program log line into one of the game's events, returning `None`
+/// for any other log line.
+pub fn decode_event(log_message: &str) -> Option<Event> {
+    let data = STANDARD
+        .decode(log_message.strip_prefix(PROGRAM_DATA)?)
+        .ok()?;
+    if data.len() < 8 {
+        return None;
+    }
+    let (discriminator, mut event_data) = data.split_at(8);
+    let event = if discriminator == get_anchor_discriminator("event:GameStarted") {
+        Event::GameStarted(GameStarted::deserialize(&mut event_data).ok()?)
+    } else if discriminator == get_anchor_discriminator("event:MovePlayed") {
+        Event::MovePlayed(MovePlayed::deserialize(&mut event_data).ok()?)
+    } else if discriminator == get_anchor_discriminator("event:GameEnded") {
+        Event::GameEnded(GameEnded::deserialize(&mut event_data).ok()?)
+    } else {
+        return None;
+    };
+    Some(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_move_played() {
+        let game = Pubkey::new_unique();
+        let player = Pubkey::new_unique();
+        let mut data = get_anchor_discriminator("event:MovePlayed").to_vec();
+        data.extend_from_slice(game.as_ref());
+        data.extend_from_slice(player.as_ref());
+        data.extend_from_slice(&[1, 2, 0, 3]);
+        let log_message = format!("{}{}", PROGRAM_DATA, STANDARD.encode(data));
+
+        assert_eq!(
+            decode_event(&log_message),
+            Some(Event::MovePlayed(MovePlayed {
+                game,
+                player,
+                tile: (1, 2),
+                layer: 0,
+                turn: 3,
+            }))
+        );
+        assert_eq!(decode_event("Program log: Game ID closed"), None);
+    }
+}
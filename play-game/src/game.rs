@@ -1,24 +1,37 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use once_cell::sync::Lazy;
-use solana_client::rpc_client::RpcClient;
-use solana_program::pubkey::Pubkey;
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_program::{hash::hashv, pubkey::Pubkey};
 use solana_sdk::{
     account::ReadableAccount,
+    compute_budget::ComputeBudgetInstruction,
     instruction::{AccountMeta, Instruction},
     signature::{Keypair, Signer},
     system_program,
     transaction::Transaction,
 };
 
-use crate::{transaction::send_transaction_and_print_logs, util, util::get_anchor_discriminator};
+use crate::{
+    config, profile, transaction::send_transaction_and_print_logs, util,
+    util::get_anchor_discriminator,
+};
 
-type Tile = (u8, u8); // (x, y) coordinates for a play
+pub type Tile = (u8, u8); // (x, y) coordinates for a play
+pub type CubeTile = (u8, u8, u8); // (layer, row, column) for a cube game play
 
 #[derive(BorshDeserialize, Debug, Copy, Clone, PartialEq, Eq)]
 pub enum GameState {
     Active,
     Tie,
     Won { winner: Pubkey },
+    Resigned { winner: Pubkey },
+    TimedOut { winner: Pubkey },
+    WaitingForOpponent,
 }
 
 #[derive(BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
@@ -27,26 +40,539 @@ pub enum Sign {
     O,
 }
 
-type Board = [[Option<Sign>; 3]; 3];
+type Board = Vec<Option<Sign>>; // row-major, `board_size * board_size` tiles
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Classic,
+    /// Tiles are (sub-board, cell) on nine 3x3 sub-boards
+    Ultimate,
+    /// Tiles are (layer, row, column) on a 4x4x4 cube, played with `play_cube`
+    Cube,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rules {
+    #[default]
+    Normal,
+    /// Whoever completes a line loses
+    Misere,
+}
+
+impl std::fmt::Display for Rules {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Rules::Normal => write!(f, "normal, completing a line wins"),
+            Rules::Misere => write!(f, "misère, completing a line loses"),
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FirstPlayer {
+    #[default]
+    PlayerOne,
+    /// Decided by a commit-reveal handshake before the first move
+    Random,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameConfig {
+    pub wager: u64,                // lamports each player puts in the pot
+    pub move_timeout: i64,         // seconds per move, 0 for no timeout
+    pub board_size: u8,            // tiles per side, 3 for classic tic-tac-toe
+    pub win_length: u8,            // signs in a row needed to win
+    pub mode: GameMode,            // 1
+    pub rules: Rules,              // 1
+    pub first_player: FirstPlayer, // 1
+    pub clock: i64,                // seconds on each player's clock, 0 for no clock
+    pub increment: i64,            // seconds added to a player's clock after each move
+}
+
+impl GameConfig {
+    pub fn tiles(&self) -> usize {
+        match self.mode {
+            GameMode::Classic => self.board_size as usize * self.board_size as usize,
+            GameMode::Ultimate => 9 * 9,
+            GameMode::Cube => 4 * 4 * 4,
+        }
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            wager: 0,
+            move_timeout: 0,
+            board_size: 3,
+            win_length: 3,
+            mode: GameMode::Classic,
+            rules: Rules::Normal,
+            first_player: FirstPlayer::PlayerOne,
+            clock: 0,
+            increment: 0,
+        }
+    }
+}
 
 static ACCOUNT_GAME_DISCRIMINATOR: Lazy<[u8; 8]> =
     Lazy::new(|| get_anchor_discriminator("account:Game"));
 
-#[derive(BorshDeserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub tile: Tile, // 2
+    pub player: u8, // 1, index into `players`
+    pub layer: u8,  // 1, cube games only
+}
+
+/// Layout version of the program's `Game` account that `GameAccount` mirrors
+pub const GAME_VERSION: u8 = 4;
+
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
 pub struct GameAccount {
-    pub players: [Pubkey; 2], // (32 * 2)
-    pub turn: u8,             // 1
-    pub board: Board,         // 9 * (1 + 1) = 18
-    pub state: GameState,     // 32 + 1
+    #[borsh(skip)]
+    pub version: u8, // 1, read by `GameAccount::decode`
+    pub players: [Pubkey; 2],               // (32 * 2)
+    pub turn: u8,                           // 1
+    pub board: Board,                       // 4 + (tiles * (1 + 1))
+    pub state: GameState,                   // 32 + 1
+    pub config: GameConfig,                 // 8 + 8 + 1 + 1 + 1 + 1 + 1 + 8 + 8
+    pub wager_matched: bool,                // 1
+    pub payer: Pubkey,                      // 32
+    pub last_move_at: i64,                  // 8
+    pub rating_changes: [i32; 2],           // 4 * 2
+    pub draw_offer: Option<Pubkey>,         // 1 + 32
+    pub moves: Vec<Move>,                   // 4 + (tiles * 4)
+    pub mint: Option<Pubkey>,               // 1 + 32, wager is in this token if set
+    pub meta_board: Vec<Option<Sign>>,      // 4 + (9 * (1 + 1)), empty unless ultimate
+    pub next_sub_board: Option<u8>,         // 1 + 1, ultimate only
+    pub first_player: u8,                   // 1, index into `players` of who plays X
+    pub commitments: [Option<[u8; 32]>; 2], // 2 * (1 + 32), random first player only
+    pub secrets: [Option<[u8; 32]>; 2],     // 2 * (1 + 32), random first player only
+    pub remaining_time: [i64; 2],           // 8 * 2, seconds left on each player's clock
+    pub takeback_request: Option<Pubkey>,   // 1 + 32
+    pub rematch_offer: Option<Pubkey>,      // 1 + 32, only once the game is over
+    pub round: u8,                          // 1, bumped by every rematch
+    pub owner: Option<Pubkey>,              // 1 + 32, the series or tournament the game counts for
+    pub scored: bool,                       // 1, once the owner has counted the result
+}
+
+/// `GameAccount` as laid out before takebacks, with or without the version
+/// byte in front
+#[derive(BorshDeserialize)]
+struct LegacyGameAccount {
+    players: [Pubkey; 2],
+    turn: u8,
+    board: Board,
+    state: GameState,
+    config: GameConfig,
+    wager_matched: bool,
+    payer: Pubkey,
+    last_move_at: i64,
+    rating_changes: [i32; 2],
+    draw_offer: Option<Pubkey>,
+    moves: Vec<Move>,
+    mint: Option<Pubkey>,
+    meta_board: Vec<Option<Sign>>,
+    next_sub_board: Option<u8>,
+    first_player: u8,
+    commitments: [Option<[u8; 32]>; 2],
+    secrets: [Option<[u8; 32]>; 2],
+    remaining_time: [i64; 2],
+}
+
+impl LegacyGameAccount {
+    fn upgrade(self, version: u8) -> GameAccount {
+        GameAccount {
+            version,
+            players: self.players,
+            turn: self.turn,
+            board: self.board,
+            state: self.state,
+            config: self.config,
+            wager_matched: self.wager_matched,
+            payer: self.payer,
+            last_move_at: self.last_move_at,
+            rating_changes: self.rating_changes,
+            draw_offer: self.draw_offer,
+            moves: self.moves,
+            mint: self.mint,
+            meta_board: self.meta_board,
+            next_sub_board: self.next_sub_board,
+            first_player: self.first_player,
+            commitments: self.commitments,
+            secrets: self.secrets,
+            remaining_time: self.remaining_time,
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
+        }
+    }
+}
+
+/// `GameAccount` as first deployed, a 3x3 game and nothing else
+#[derive(BorshDeserialize)]
+struct OriginalGameAccount {
+    players: [Pubkey; 2],
+    turn: u8,
+    board: [[Option<Sign>; 3]; 3],
+    state: GameState,
+}
+
+impl OriginalGameAccount {
+    /// Accounts were allocated for exactly this much after the discriminator
+    const SIZE: usize = (32 * 2) + 1 + (9 * (1 + 1)) + (32 + 1);
+
+    fn upgrade(self) -> GameAccount {
+        LegacyGameAccount {
+            players: self.players,
+            turn: self.turn,
+            board: self.board.concat(),
+            state: self.state,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: self.players[0],
+            last_move_at: 0,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            moves: vec![],
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+        }
+        .upgrade(0)
+    }
+}
+
+/// Version 2 of `GameAccount` added takebacks
+#[derive(BorshDeserialize)]
+struct GameAccountV2 {
+    game: LegacyGameAccount,
+    takeback_request: Option<Pubkey>,
+}
+
+/// Version 3 of `GameAccount` added rematches
+#[derive(BorshDeserialize)]
+struct GameAccountV3 {
+    game: LegacyGameAccount,
+    takeback_request: Option<Pubkey>,
+    rematch_offer: Option<Pubkey>,
+    round: u8,
+}
+
+fn sign_char(tile: Option<Sign>) -> char {
+    match tile {
+        Some(Sign::X) => 'X',
+        Some(Sign::O) => 'O',
+        None => '.',
+    }
+}
+
+impl GameAccount {
+    /// Decodes a game stored in any layout the program has used, `data`
+    /// following the account discriminator.
+    pub fn decode(data: &[u8]) -> GameAccount {
+        match data {
+            _ if data.len() == OriginalGameAccount::SIZE => {
+                OriginalGameAccount::deserialize(&mut &data[..])
+                    .unwrap()
+                    .upgrade()
+            }
+            [GAME_VERSION, rest @ ..] if GameAccount::deserialize(&mut &rest[..]).is_ok() => {
+                let mut game_account = GameAccount::deserialize(&mut &rest[..]).unwrap();
+                game_account.version = GAME_VERSION;
+                game_account
+            }
+            [3, rest @ ..] if GameAccountV3::deserialize(&mut &rest[..]).is_ok() => {
+                let v3 = GameAccountV3::deserialize(&mut &rest[..]).unwrap();
+                GameAccount {
+                    takeback_request: v3.takeback_request,
+                    rematch_offer: v3.rematch_offer,
+                    round: v3.round,
+                    ..v3.game.upgrade(3)
+                }
+            }
+            [2, rest @ ..] if GameAccountV2::deserialize(&mut &rest[..]).is_ok() => {
+                let v2 = GameAccountV2::deserialize(&mut &rest[..]).unwrap();
+                GameAccount {
+                    takeback_request: v2.takeback_request,
+                    ..v2.game.upgrade(2)
+                }
+            }
+            [1, rest @ ..] if LegacyGameAccount::deserialize(&mut &rest[..]).is_ok() => {
+                LegacyGameAccount::deserialize(&mut &rest[..])
+                    .unwrap()
+                    .upgrade(1)
+            }
+            // Games from before the version byte start straight with the players
+            _ => LegacyGameAccount::deserialize(&mut &data[..])
+                .unwrap()
+                .upgrade(0),
+        }
+    }
+
+    /// Index into `players` of whose turn it is
+    pub fn current_player_index(&self) -> usize {
+        (((self.turn - 1) ^ self.first_player) & 1) as usize
+    }
+
+    /// Draws the board, laying out an ultimate game's sub-boards like the
+    /// meta-board they make up and a cube's layers side by side
+    pub fn print_board(&self) {
+        match self.config.mode {
+            GameMode::Cube => {
+                for row in 0..4 {
+                    let line: Vec<String> = (0..4)
+                        .map(|layer| {
+                            let start = layer * 16 + row * 4;
+                            self.board[start..start + 4]
+                                .iter()
+                                .map(|&tile| sign_char(tile))
+                                .collect()
+                        })
+                        .collect();
+                    println!("  {}", line.join(" | "));
+                }
+            }
+            GameMode::Classic => {
+                for row in self.board.chunks(self.config.board_size as usize) {
+                    println!(
+                        "  {}",
+                        row.iter().map(|&tile| sign_char(tile)).collect::<String>()
+                    );
+                }
+            }
+            GameMode::Ultimate => {
+                for meta_row in 0..3 {
+                    for cell_row in 0..3 {
+                        let line: Vec<String> = (0..3)
+                            .map(|meta_col| {
+                                let start = (meta_row * 3 + meta_col) * 9 + cell_row * 3;
+                                self.board[start..start + 3]
+                                    .iter()
+                                    .map(|&tile| sign_char(tile))
+                                    .collect()
+                            })
+                            .collect();
+                        println!("  {}", line.join(" | "));
+                    }
+                    if meta_row < 2 {
+                        println!("  ----+-----+----");
+                    }
+                }
+                let meta_board: String = self
+                    .meta_board
+                    .iter()
+                    .map(|&tile| sign_char(tile))
+                    .collect();
+                println!("Meta-board: {}", meta_board);
+                if let Some(sub_board) = self.next_sub_board {
+                    println!("Next move goes in sub-board {}", sub_board);
+                }
+            }
+        }
+    }
+
+    pub fn print_moves(&self) {
+        println!("Moves:");
+        for (i, m) in self.moves.iter().enumerate() {
+            if self.config.mode == GameMode::Cube {
+                println!(
+                    "  {}. player {} plays ({}, {}, {})",
+                    i + 1,
+                    m.player + 1,
+                    m.layer,
+                    m.tile.0,
+                    m.tile.1
+                );
+            } else {
+                println!(
+                    "  {}. player {} plays ({}, {})",
+                    i + 1,
+                    m.player + 1,
+                    m.tile.0,
+                    m.tile.1
+                );
+            }
+        }
+    }
+}
+
+/// Games in the current layout where `player` holds either seat, for
+/// finding games the program set up for them, like those of a queue.
+pub fn find_games(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    player: &Pubkey,
+) -> Vec<(Pubkey, GameAccount)> {
+    // The players follow the discriminator and the version byte
+    let players_offset = 8 + 1;
+    [0, 1]
+        .into_iter()
+        .flat_map(|seat| {
+            let config = RpcProgramAccountsConfig {
+                filters: Some(vec![
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                        0,
+                        [ACCOUNT_GAME_DISCRIMINATOR.as_slice(), &[GAME_VERSION]].concat(),
+                    )),
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                        players_offset + (seat * 32),
+                        player.to_bytes().to_vec(),
+                    )),
+                ]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    ..RpcAccountInfoConfig::default()
+                },
+                ..RpcProgramAccountsConfig::default()
+            };
+            rpc_client
+                .get_program_accounts_with_config(program_id, config)
+                .unwrap()
+        })
+        .map(|(address, account)| (address, GameAccount::decode(&account.data()[8..])))
+        .collect()
+}
+
+/// Converts a game in an older layout to the latest one, with `payer`
+/// covering any extra rent.
+pub fn migrate_game(rpc_client: &RpcClient, program_id: &Pubkey, payer: &Keypair, game: &Pubkey) {
+    let instruction_data = get_anchor_discriminator("global:migrate_game").to_vec();
+
+    let migrate_game_instruction = Instruction::new_with_bytes(
+        *program_id,
+        &instruction_data,
+        vec![
+            AccountMeta::new(*game, false),
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[migrate_game_instruction],
+        Some(&payer.pubkey()),
+        &[payer],
+        rpc_client.get_latest_blockhash().unwrap(),
+    );
+    if let Err(e) = send_transaction_and_print_logs(rpc_client, &transaction) {
+        eprintln!("Error migrating game: {:?}", e);
+        std::process::exit(1);
+    }
+}
+
+fn request_takeback_instruction(
+    program_id: &Pubkey,
+    game: &Pubkey,
+    player: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &get_anchor_discriminator("global:request_takeback"),
+        vec![
+            AccountMeta::new(*game, false),
+            AccountMeta::new_readonly(*player, true),
+        ],
+    )
+}
+
+/// The profiles are only passed along when the move being taken back ended
+/// the game and its result has to be undone. Before that a player may not
+/// have a profile yet, and the program's id stands in for them.
+fn accept_takeback_instruction(
+    program_id: &Pubkey,
+    game: &Pubkey,
+    player: &Pubkey,
+    game_account: &GameAccount,
+) -> Instruction {
+    let profiles = if game_account.state == GameState::Active {
+        vec![AccountMeta::new_readonly(*program_id, false); 2]
+    } else {
+        game_account
+            .players
+            .iter()
+            .map(|seat| AccountMeta::new(profile::profile_address(program_id, seat), false))
+            .collect()
+    };
+    Instruction::new_with_bytes(
+        *program_id,
+        &get_anchor_discriminator("global:accept_takeback"),
+        [
+            vec![
+                AccountMeta::new(*game, false),
+                AccountMeta::new_readonly(*player, true),
+            ],
+            profiles,
+        ]
+        .concat(),
+    )
+}
+
+/// Requests a takeback of `player`'s last move in `game`, or with `accept`
+/// takes back the opponent's last move at their request.
+pub fn takeback(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    player: &Keypair,
+    game: &Pubkey,
+    accept: bool,
+) {
+    let instruction = if accept {
+        let data = rpc_client.get_account_data(game).unwrap();
+        let game_account = GameAccount::decode(&data[8..]);
+        accept_takeback_instruction(program_id, game, &player.pubkey(), &game_account)
+    } else {
+        request_takeback_instruction(program_id, game, &player.pubkey())
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&player.pubkey()),
+        &[player],
+        rpc_client.get_latest_blockhash().unwrap(),
+    );
+    if let Err(e) = send_transaction_and_print_logs(rpc_client, &transaction) {
+        eprintln!("Error with takeback: {:?}", e);
+        std::process::exit(1);
+    }
 }
 
 pub struct Game<'a> {
     pub program_id: &'a Pubkey,
     pub rpc_client: &'a RpcClient,
     pub print_balances: bool,
+    pub config: GameConfig,
+    pub open_challenge: bool,
+    /// When set, the game lives at the PDA derived from the players and this
+    /// nonce instead of at `game_keypair`.
+    pub pda_nonce: Option<u64>,
     pub game_keypair: Keypair,
     pub player_one: &'a Keypair,
     pub player_two: &'a Keypair,
+    /// Set for a single-player game, where the program's own PDA takes the
+    /// second seat and `player_two` is never used
+    pub against_program: bool,
+    /// Set for games the program sets up at an address of its own choosing,
+    /// like the games of a series or a tournament
+    pub address: Option<Pubkey>,
+}
+
+/// Compute units requested for a move against the program, which searches
+/// for its reply in the same instruction. The first reply searches up to 640
+/// positions, which can take more than the default 200k units.
+const PROGRAM_REPLY_COMPUTE_UNITS: u32 = 400_000;
+
+/// The program's seat in a single-player game
+pub fn program_player(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"ai"], program_id).0
 }
 
 impl<'a> Game<'a> {
@@ -60,18 +586,108 @@ impl<'a> Game<'a> {
             program_id,
             rpc_client,
             print_balances: true,
+            config: GameConfig::default(),
+            open_challenge: false,
+            pda_nonce: None,
             game_keypair: Keypair::new(),
             player_one,
             player_two,
+            against_program: false,
+            address: None,
         }
     }
 
+    /// A game of `player` against the program itself
+    pub fn against_program(
+        program_id: &'a Pubkey,
+        rpc_client: &'a RpcClient,
+        player: &'a Keypair,
+    ) -> Self {
+        Self {
+            against_program: true,
+            ..Self::new(program_id, rpc_client, player, player)
+        }
+    }
+
+    /// Derives the address of a game created by `setup_pda_game`. Open
+    /// challenges are derived with the default pubkey as player two.
+    pub fn pda_address(
+        program_id: &Pubkey,
+        player_one: &Pubkey,
+        player_two: Option<&Pubkey>,
+        nonce: u64,
+    ) -> Pubkey {
+        let player_two = player_two.copied().unwrap_or_default();
+        Pubkey::find_program_address(
+            &[
+                b"game",
+                player_one.as_ref(),
+                player_two.as_ref(),
+                &nonce.to_le_bytes(),
+            ],
+            program_id,
+        )
+        .0
+    }
+
     pub fn game_id(&self) -> Pubkey {
-        self.game_keypair.pubkey()
+        if let Some(address) = self.address {
+            return address;
+        }
+        match self.pda_nonce {
+            Some(nonce) => Self::pda_address(
+                self.program_id,
+                &self.player_one.pubkey(),
+                self.player_two_seat().as_ref(),
+                nonce,
+            ),
+            None => self.game_keypair.pubkey(),
+        }
+    }
+
+    /// Open challenges leave the second seat for whoever joins first
+    fn player_two_seat(&self) -> Option<Pubkey> {
+        (!self.open_challenge).then(|| self.players()[1])
     }
 
     pub fn players(&self) -> [Pubkey; 2] {
-        [self.player_one.pubkey(), self.player_two.pubkey()]
+        let player_two = if self.against_program {
+            program_player(self.program_id)
+        } else {
+            self.player_two.pubkey()
+        };
+        [self.player_one.pubkey(), player_two]
+    }
+
+    /// Placeholders for optional accounts the program takes for SPL token
+    /// wagers, which this client only plays with SOL.
+    pub(crate) fn token_accounts_omitted(&self, count: usize) -> Vec<AccountMeta> {
+        vec![AccountMeta::new_readonly(*self.program_id, false); count]
+    }
+
+    /// Accounts of the program's `Settlement`, which pays out and records the
+    /// result when an instruction ends the game.
+    fn settlement_accounts(&self, payer: &Pubkey) -> Vec<AccountMeta> {
+        let [player_one, player_two] = self.players();
+        [
+            vec![
+                AccountMeta::new(player_one, false),
+                AccountMeta::new(player_two, false),
+                AccountMeta::new(
+                    profile::profile_address(self.program_id, &player_one),
+                    false,
+                ),
+                AccountMeta::new(
+                    profile::profile_address(self.program_id, &player_two),
+                    false,
+                ),
+                AccountMeta::new_readonly(config::config_address(self.program_id), false),
+                AccountMeta::new(*payer, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+            self.token_accounts_omitted(6),
+        ]
+        .concat()
     }
 
     pub fn print_balance(&self, name: &str, public_key: &Pubkey) {
@@ -83,9 +699,51 @@ impl<'a> Game<'a> {
         let account_data = game_state.data();
         assert!(account_data.len() > 8);
         let set_discriminator = &account_data[0..8];
-        let mut game_state = &account_data[8..];
         assert_eq!(set_discriminator, *ACCOUNT_GAME_DISCRIMINATOR);
-        GameAccount::deserialize(&mut game_state).unwrap()
+        GameAccount::decode(&account_data[8..])
+    }
+
+    /// Sends `instruction` in a transaction paid for by `signer` and returns
+    /// the transaction fee.
+    fn send_instruction(&self, instruction: Instruction, signer: &Keypair, action: &str) -> u64 {
+        let recent_block_hash = self.rpc_client.get_latest_blockhash().unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&signer.pubkey()),
+            &vec![signer],
+            recent_block_hash,
+        );
+        let fee = self
+            .rpc_client
+            .get_fee_for_message(&transaction.message)
+            .unwrap();
+
+        match send_transaction_and_print_logs(self.rpc_client, &transaction) {
+            Ok(_) => (),
+            Err(e) => {
+                eprintln!("Error {}: {:?}", action, e);
+                std::process::exit(1);
+            }
+        }
+
+        fee
+    }
+
+    /// Fetches the game after a move, showing how the ratings moved if the
+    /// move ended the game.
+    fn get_game_account_after_move(&self) -> GameAccount {
+        let game_account = self.get_game_account();
+        if game_account.state != GameState::Active {
+            game_account.print_moves();
+            let players = ["player one", "player two"].into_iter().zip(self.players());
+            for ((name, player), change) in players.zip(game_account.rating_changes) {
+                let rating = profile::get_player_profile(self.rpc_client, self.program_id, &player)
+                    .unwrap()
+                    .rating;
+                println!("Rating of {}: {} ({:+})", name, rating, change);
+            }
+        }
+        game_account
     }
 
     pub fn setup_game(&self) {
@@ -94,19 +752,30 @@ impl<'a> Game<'a> {
             self.print_balance("player two at start", &self.player_two.pubkey());
         }
 
-        let player_two_pub: Pubkey = self.player_two.pubkey();
+        let player_two_pub: Option<Pubkey> = self.player_two_seat();
 
-        let mut instruction_data = get_anchor_discriminator("global:setup_game").to_vec();
+        let mut instruction_data = match self.pda_nonce {
+            Some(_) => get_anchor_discriminator("global:setup_pda_game").to_vec(),
+            None => get_anchor_discriminator("global:setup_game").to_vec(),
+        };
         player_two_pub.serialize(&mut instruction_data).unwrap();
+        if let Some(nonce) = self.pda_nonce {
+            nonce.serialize(&mut instruction_data).unwrap();
+        }
+        self.config.serialize(&mut instruction_data).unwrap();
 
         let setup_game_instruction = Instruction::new_with_bytes(
             *self.program_id,
             &instruction_data,
-            vec![
-                AccountMeta::new(self.game_id(), true),
-                AccountMeta::new(self.player_one.pubkey(), true),
-                AccountMeta::new_readonly(system_program::id(), false),
-            ],
+            [
+                vec![
+                    AccountMeta::new(self.game_id(), self.pda_nonce.is_none()),
+                    AccountMeta::new(self.player_one.pubkey(), true),
+                ],
+                self.token_accounts_omitted(6),
+                vec![AccountMeta::new_readonly(system_program::id(), false)],
+            ]
+            .concat(),
         );
 
         let recent_block_hash = self.rpc_client.get_latest_blockhash().unwrap();
@@ -114,7 +783,11 @@ impl<'a> Game<'a> {
 
         let mut transaction =
             Transaction::new_with_payer(&[setup_game_instruction], Some(&payer.pubkey()));
-        transaction.sign(&[payer, &self.game_keypair], recent_block_hash);
+        // Only keypair games need the game account's signature
+        match self.pda_nonce {
+            Some(_) => transaction.sign(&[payer], recent_block_hash),
+            None => transaction.sign(&[payer, &self.game_keypair], recent_block_hash),
+        }
 
         match send_transaction_and_print_logs(self.rpc_client, &transaction) {
             Ok(_) => (),
@@ -127,9 +800,19 @@ impl<'a> Game<'a> {
         let game_state = self.get_game_account();
         assert_eq!(game_state.turn, 1);
         assert_eq!(game_state.players[0], self.player_one.pubkey());
-        assert_eq!(game_state.players[1], self.player_two.pubkey());
-        assert_eq!(game_state.state, GameState::Active);
-        assert_eq!(game_state.board, [[None; 3]; 3]);
+        if self.open_challenge {
+            assert_eq!(game_state.players[1], Pubkey::default());
+            assert_eq!(game_state.state, GameState::WaitingForOpponent);
+        } else {
+            assert_eq!(game_state.players[1], self.players()[1]);
+            assert_eq!(game_state.state, GameState::Active);
+        }
+        assert_eq!(game_state.board, vec![None; self.config.tiles()]);
+        assert_eq!(game_state.config, self.config);
+        assert!(!game_state.wager_matched);
+        assert_eq!(game_state.payer, self.player_one.pubkey());
+        assert_eq!(game_state.mint, None);
+        println!("Rules: {}", game_state.config.rules);
 
         if self.print_balances {
             self.print_balance("game after setup", &self.game_id());
@@ -138,8 +821,78 @@ impl<'a> Game<'a> {
         }
     }
 
+    pub fn join_game(&self) {
+        let instruction_data = get_anchor_discriminator("global:join_game").to_vec();
+
+        let join_game_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            [
+                vec![
+                    AccountMeta::new(self.game_id(), false),
+                    AccountMeta::new(self.player_two.pubkey(), true),
+                ],
+                self.token_accounts_omitted(6),
+                vec![AccountMeta::new_readonly(system_program::id(), false)],
+            ]
+            .concat(),
+        );
+
+        self.send_instruction(join_game_instruction, self.player_two, "joining game");
+
+        let game_state = self.get_game_account();
+        assert_eq!(game_state.players, self.players());
+        assert_eq!(game_state.state, GameState::Active);
+        assert_eq!(game_state.wager_matched, self.config.wager > 0);
+
+        if self.print_balances {
+            self.print_balance("game after join", &self.game_id());
+            self.print_balance("player two after join", &self.player_two.pubkey());
+        }
+    }
+
+    pub fn match_wager(&self) {
+        let instruction_data = get_anchor_discriminator("global:match_wager").to_vec();
+
+        let match_wager_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            [
+                vec![
+                    AccountMeta::new(self.game_id(), false),
+                    AccountMeta::new(self.player_two.pubkey(), true),
+                ],
+                self.token_accounts_omitted(6),
+                vec![AccountMeta::new_readonly(system_program::id(), false)],
+            ]
+            .concat(),
+        );
+
+        self.send_instruction(match_wager_instruction, self.player_two, "matching wager");
+
+        assert!(self.get_game_account().wager_matched);
+
+        if self.print_balances {
+            self.print_balance("game after wager matched", &self.game_id());
+            self.print_balance("player two after wager matched", &self.player_two.pubkey());
+        }
+    }
+
     pub fn play(&mut self, tile: Tile) -> GameAccount {
-        let is_player_one = self.get_game_account().turn % 2 == 1;
+        let mut instruction_data = get_anchor_discriminator("global:play").to_vec();
+        tile.serialize(&mut instruction_data).unwrap();
+        self.play_move(instruction_data)
+    }
+
+    pub fn play_cube(&mut self, tile: CubeTile) -> GameAccount {
+        let mut instruction_data = get_anchor_discriminator("global:play_cube").to_vec();
+        tile.serialize(&mut instruction_data).unwrap();
+        self.play_move(instruction_data)
+    }
+
+    /// Sends a `play` or `play_cube` instruction for whoever's turn it is.
+    fn play_move(&mut self, instruction_data: Vec<u8>) -> GameAccount {
+        let is_player_one = self.get_game_account().current_player_index() == 0;
         let player_pub_key = if is_player_one {
             self.player_one.pubkey()
         } else {
@@ -150,30 +903,40 @@ impl<'a> Game<'a> {
             self.print_balance("before play", &player_pub_key);
         }
 
-        let mut instruction_data = get_anchor_discriminator("global:play").to_vec();
-        tile.serialize(&mut instruction_data).unwrap();
-
         let play_instruction = Instruction::new_with_bytes(
             *self.program_id,
             &instruction_data,
-            vec![
-                AccountMeta::new(self.game_id(), false),
-                AccountMeta::new(player_pub_key, true),
-            ],
+            [
+                vec![
+                    AccountMeta::new(self.game_id(), false),
+                    AccountMeta::new(player_pub_key, true),
+                ],
+                self.settlement_accounts(&player_pub_key),
+            ]
+            .concat(),
         );
 
+        let instructions = if self.against_program {
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(PROGRAM_REPLY_COMPUTE_UNITS),
+                play_instruction,
+            ]
+        } else {
+            vec![play_instruction]
+        };
+
         let recent_block_hash = self.rpc_client.get_latest_blockhash().unwrap();
 
         let transaction = if is_player_one {
             Transaction::new_signed_with_payer(
-                &[play_instruction],
+                &instructions,
                 Some(&self.player_one.pubkey()),
                 &vec![&self.player_one],
                 recent_block_hash,
             )
         } else {
             Transaction::new_signed_with_payer(
-                &[play_instruction],
+                &instructions,
                 None,
                 &vec![&self.player_two],
                 recent_block_hash,
@@ -194,6 +957,227 @@ impl<'a> Game<'a> {
             self.print_balance("player two after play", &self.player_two.pubkey());
         }
 
+        let game_account = self.get_game_account_after_move();
+        game_account.print_board();
+        game_account
+    }
+
+    pub fn claim_timeout(&self, player: &Keypair) -> GameAccount {
+        let instruction_data = get_anchor_discriminator("global:claim_timeout").to_vec();
+
+        let claim_timeout_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            [
+                vec![
+                    AccountMeta::new(self.game_id(), false),
+                    AccountMeta::new(player.pubkey(), true),
+                ],
+                self.settlement_accounts(&player.pubkey()),
+            ]
+            .concat(),
+        );
+
+        self.send_instruction(claim_timeout_instruction, player, "claiming timeout");
+
+        self.get_game_account_after_move()
+    }
+
+    /// Flags the player to move once their clock has run out. Anyone can
+    /// send this, `payer` only pays the fees and for any missing profiles.
+    pub fn flag(&self, payer: &Keypair) -> GameAccount {
+        let instruction_data = get_anchor_discriminator("global:flag").to_vec();
+
+        let flag_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            [
+                vec![AccountMeta::new(self.game_id(), false)],
+                self.settlement_accounts(&payer.pubkey()),
+            ]
+            .concat(),
+        );
+
+        self.send_instruction(flag_instruction, payer, "flagging");
+
+        self.get_game_account_after_move()
+    }
+
+    /// Commits `player` to `secret` for picking a random first player.
+    pub fn commit_secret(&self, player: &Keypair, secret: &[u8; 32]) -> GameAccount {
+        let commitment = hashv(&[secret, player.pubkey().as_ref()]).to_bytes();
+        let mut instruction_data = get_anchor_discriminator("global:commit_secret").to_vec();
+        commitment.serialize(&mut instruction_data).unwrap();
+
+        let commit_secret_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(self.game_id(), false),
+                AccountMeta::new_readonly(player.pubkey(), true),
+            ],
+        );
+
+        self.send_instruction(commit_secret_instruction, player, "committing secret");
+
+        self.get_game_account()
+    }
+
+    pub fn reveal_secret(&self, player: &Keypair, secret: &[u8; 32]) -> GameAccount {
+        let mut instruction_data = get_anchor_discriminator("global:reveal_secret").to_vec();
+        secret.serialize(&mut instruction_data).unwrap();
+
+        let reveal_secret_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(self.game_id(), false),
+                AccountMeta::new_readonly(player.pubkey(), true),
+            ],
+        );
+
+        self.send_instruction(reveal_secret_instruction, player, "revealing secret");
+
+        self.get_game_account()
+    }
+
+    pub fn offer_draw(&self, player: &Keypair) -> GameAccount {
+        let instruction_data = get_anchor_discriminator("global:offer_draw").to_vec();
+
+        let offer_draw_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(self.game_id(), false),
+                AccountMeta::new_readonly(player.pubkey(), true),
+            ],
+        );
+
+        self.send_instruction(offer_draw_instruction, player, "offering draw");
+
+        self.get_game_account()
+    }
+
+    pub fn accept_draw(&self, player: &Keypair) -> GameAccount {
+        let instruction_data = get_anchor_discriminator("global:accept_draw").to_vec();
+
+        let accept_draw_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            [
+                vec![
+                    AccountMeta::new(self.game_id(), false),
+                    AccountMeta::new(player.pubkey(), true),
+                ],
+                self.settlement_accounts(&player.pubkey()),
+            ]
+            .concat(),
+        );
+
+        self.send_instruction(accept_draw_instruction, player, "accepting draw");
+
+        self.get_game_account_after_move()
+    }
+
+    /// Asks the opponent to take back `player`'s last move.
+    pub fn request_takeback(&self, player: &Keypair) -> GameAccount {
+        let instruction =
+            request_takeback_instruction(self.program_id, &self.game_id(), &player.pubkey());
+
+        self.send_instruction(instruction, player, "requesting takeback");
+
+        self.get_game_account()
+    }
+
+    pub fn accept_takeback(&self, player: &Keypair) -> GameAccount {
+        let instruction = accept_takeback_instruction(
+            self.program_id,
+            &self.game_id(),
+            &player.pubkey(),
+            &self.get_game_account(),
+        );
+
+        self.send_instruction(instruction, player, "accepting takeback");
+
+        self.get_game_account()
+    }
+
+    /// Offers a rematch of the finished game, or accepts the opponent's
+    /// offer, which starts the next round with X and O swapped.
+    pub fn rematch(&self, player: &Keypair) -> GameAccount {
+        let instruction_data = get_anchor_discriminator("global:rematch").to_vec();
+
+        let rematch_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(self.game_id(), false),
+                AccountMeta::new_readonly(player.pubkey(), true),
+            ],
+        );
+
+        self.send_instruction(rematch_instruction, player, "rematching");
+
         self.get_game_account()
     }
+
+    pub fn resign(&self, player: &Keypair) -> GameAccount {
+        let instruction_data = get_anchor_discriminator("global:resign").to_vec();
+
+        let resign_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            [
+                vec![
+                    AccountMeta::new(self.game_id(), false),
+                    AccountMeta::new(player.pubkey(), true),
+                ],
+                self.settlement_accounts(&player.pubkey()),
+            ]
+            .concat(),
+        );
+
+        self.send_instruction(resign_instruction, player, "resigning");
+
+        self.get_game_account_after_move()
+    }
+
+    pub fn close_game(&self) {
+        let game_balance = self.rpc_client.get_balance(&self.game_id()).unwrap();
+        let payer_balance = self
+            .rpc_client
+            .get_balance(&self.player_one.pubkey())
+            .unwrap();
+
+        let instruction_data = get_anchor_discriminator("global:close_game").to_vec();
+
+        let close_game_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            [
+                vec![
+                    AccountMeta::new(self.game_id(), false),
+                    AccountMeta::new(self.player_one.pubkey(), true),
+                ],
+                self.token_accounts_omitted(5),
+            ]
+            .concat(),
+        );
+
+        let fee = self.send_instruction(close_game_instruction, self.player_one, "closing game");
+
+        // The game's rent goes back to the player who paid for it
+        let err = self.rpc_client.get_account(&self.game_id()).err().unwrap();
+        assert!(err.to_string().contains("AccountNotFound"));
+        assert_eq!(
+            self.rpc_client
+                .get_balance(&self.player_one.pubkey())
+                .unwrap(),
+            payer_balance + game_balance - fee
+        );
+
+        if self.print_balances {
+            self.print_balance("player one after close", &self.player_one.pubkey());
+        }
+    }
 }
@@ -1,10 +1,19 @@
+mod config;
+mod events;
 mod game;
+mod profile;
+mod queue;
+mod series;
+mod single_player;
 mod tests;
+mod tournament;
 mod transaction;
 mod util;
 
+use std::str::FromStr;
+
 use solana_client::rpc_client::RpcClient;
-use solana_program::native_token;
+use solana_program::{native_token, pubkey::Pubkey};
 use solana_sdk::{commitment_config::CommitmentConfig, signature::Keypair, signer::Signer};
 use util::{get_payer_key, get_program_id};
 
@@ -12,20 +21,89 @@ use crate::util::get_rpc_url;
 
 fn main() {
     let program_id = get_program_id();
-    let payer = get_payer_key();
     let rpc_client = RpcClient::new_with_commitment(get_rpc_url(), CommitmentConfig::processed());
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [] => play_test_games(&program_id, &rpc_client),
+        ["profile", player] => {
+            let player = Pubkey::from_str(player).unwrap();
+            profile::print_player_profile(&rpc_client, &program_id, &player);
+        }
+        ["vs-program"] => {
+            single_player::play_against_program(&program_id, &rpc_client, &get_payer_key());
+        }
+        ["migrate", game] => {
+            let game = Pubkey::from_str(game).unwrap();
+            game::migrate_game(&rpc_client, &program_id, &get_payer_key(), &game);
+        }
+        ["find-game"] => {
+            queue::find_game(&program_id, &rpc_client, &get_payer_key(), None);
+        }
+        ["find-game", band] => {
+            let band = band.parse().unwrap();
+            queue::find_game(&program_id, &rpc_client, &get_payer_key(), Some(band));
+        }
+        ["tournament"] => {
+            tournament::play_tournament(&program_id, &rpc_client, &get_payer_key());
+        }
+        ["takeback", action @ ("request" | "accept"), game] => {
+            let game = Pubkey::from_str(game).unwrap();
+            let accept = *action == "accept";
+            game::takeback(&rpc_client, &program_id, &get_payer_key(), &game, accept);
+        }
+        _ => {
+            eprintln!(
+                "Usage: play-game [profile <PLAYER_PUBKEY> | vs-program | migrate <GAME_PUBKEY> \
+                 | takeback <request|accept> <GAME_PUBKEY> | tournament | find-game [RATING_BAND]]"
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+fn play_test_games(program_id: &Pubkey, rpc_client: &RpcClient) {
+    let payer = get_payer_key();
+
+    config::initialize_config_if_needed(rpc_client, program_id, &payer, config::DEFAULT_K_FACTOR);
+
     // Fund the players
     let player_one = Keypair::new();
     let player_two = Keypair::new();
     let lamports = native_token::sol_to_lamports(0.01);
-    transaction::transfer(&rpc_client, lamports, &payer, &player_one.pubkey());
-    transaction::transfer(&rpc_client, lamports, &payer, &player_two.pubkey());
+    transaction::transfer(rpc_client, lamports, &payer, &player_one.pubkey());
+    transaction::transfer(rpc_client, lamports, &payer, &player_two.pubkey());
+
+    tests::play_player_one_wins_game(program_id, rpc_client, &player_one, &player_two);
+    tests::tie_game(program_id, rpc_client, &player_one, &player_two);
+    tests::player_two_wins_wager_game(program_id, rpc_client, &player_one, &player_two);
+    tests::player_one_resigns_game(program_id, rpc_client, &player_one, &player_two);
+    tests::draw_by_agreement_game(program_id, rpc_client, &player_one, &player_two);
+    tests::takeback_game(program_id, rpc_client, &player_one, &player_two);
+    tests::rematch_game(program_id, rpc_client, &player_one, &player_two);
+    tests::player_two_wins_on_timeout_game(program_id, rpc_client, &player_one, &player_two);
+    tests::player_one_loses_on_time_game(program_id, rpc_client, &player_one, &player_two);
+    tests::open_challenge_game(program_id, rpc_client, &player_one, &player_two);
+    tests::larger_board_game(program_id, rpc_client, &player_one, &player_two);
+    tests::ultimate_game(program_id, rpc_client, &player_one, &player_two);
+    tests::cube_game(program_id, rpc_client, &player_one, &player_two);
+    tests::misere_game(program_id, rpc_client, &player_one, &player_two);
+    tests::random_first_player_game(program_id, rpc_client, &player_one, &player_two);
+    tests::program_opponent_game(program_id, rpc_client, &player_one);
+    tests::pda_game(program_id, rpc_client, &player_one, &player_two);
+    tests::best_of_three_series(program_id, rpc_client, &player_one, &player_two);
+    tests::series_forfeit(program_id, rpc_client, &player_one, &player_two);
+    tests::queue_game(program_id, rpc_client, &player_one, &player_two);
 
-    tests::play_player_one_wins_game(&program_id, &rpc_client, &player_one, &player_two);
-    tests::tie_game(&program_id, &rpc_client, &player_one, &player_two);
+    profile::print_player_profile(rpc_client, program_id, &player_one.pubkey());
+    profile::print_player_profile(rpc_client, program_id, &player_two.pubkey());
 
     // Sweep funds back from temporary accounts before they disappear
-    transaction::sweep(&rpc_client, &player_one, &payer.pubkey());
-    transaction::sweep(&rpc_client, &player_two, &payer.pubkey());
+    transaction::sweep(rpc_client, &player_one, &payer.pubkey());
+    transaction::sweep(rpc_client, &player_two, &payer.pubkey());
 }
@@ -0,0 +1,66 @@
+use borsh::BorshDeserialize;
+use once_cell::sync::Lazy;
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::ReadableAccount;
+
+use crate::util::get_anchor_discriminator;
+
+static ACCOUNT_PLAYER_PROFILE_DISCRIMINATOR: Lazy<[u8; 8]> =
+    Lazy::new(|| get_anchor_discriminator("account:PlayerProfile"));
+
+const INITIAL_RATING: u32 = 1200;
+
+#[derive(BorshDeserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PlayerProfileAccount {
+    pub player: Pubkey, // 32
+    pub wins: u32,      // 4
+    pub losses: u32,    // 4
+    pub ties: u32,      // 4
+    pub rating: u32,    // 4
+}
+
+/// The profile of a player who hasn't finished a game yet
+impl Default for PlayerProfileAccount {
+    fn default() -> Self {
+        Self {
+            player: Pubkey::default(),
+            wins: 0,
+            losses: 0,
+            ties: 0,
+            rating: INITIAL_RATING,
+        }
+    }
+}
+
+pub fn profile_address(program_id: &Pubkey, player: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"profile", player.as_ref()], program_id).0
+}
+
+/// Returns `None` for players that haven't finished a game yet.
+pub fn get_player_profile(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    player: &Pubkey,
+) -> Option<PlayerProfileAccount> {
+    let account = rpc_client
+        .get_account(&profile_address(program_id, player))
+        .ok()?;
+    let account_data = account.data();
+    assert!(account_data.len() > 8);
+    assert_eq!(&account_data[0..8], *ACCOUNT_PLAYER_PROFILE_DISCRIMINATOR);
+    let profile = PlayerProfileAccount::deserialize(&mut &account_data[8..]).unwrap();
+    // The program allocates profiles during a player's first game, but
+    // only fills them in once that game is over.
+    (profile.player != Pubkey::default()).then_some(profile)
+}
+
+pub fn print_player_profile(rpc_client: &RpcClient, program_id: &Pubkey, player: &Pubkey) {
+    match get_player_profile(rpc_client, program_id, player) {
+        Some(profile) => println!(
+            "Player {}: rating {}, {} wins, {} losses, {} ties",
+            player, profile.rating, profile.wins, profile.losses, profile.ties
+        ),
+        None => println!("Player {} has no profile yet", player),
+    }
+}
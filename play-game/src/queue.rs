@@ -0,0 +1,227 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use once_cell::sync::Lazy;
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{
+    account::ReadableAccount,
+    instruction::{AccountMeta, Instruction},
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::Transaction,
+};
+
+use crate::{
+    game::{self, GameConfig, GameState},
+    profile,
+    transaction::send_transaction_and_print_logs,
+    util::get_anchor_discriminator,
+};
+
+static ACCOUNT_QUEUE_DISCRIMINATOR: Lazy<[u8; 8]> =
+    Lazy::new(|| get_anchor_discriminator("account:Queue"));
+
+/// The queue `find-game` joins when none is given
+pub const DEFAULT_QUEUE_ID: u64 = 0;
+
+#[derive(BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueEntry {
+    pub player: Pubkey,    // 32
+    pub rating: u32,       // 4, when enqueueing
+    pub band: Option<u32>, // 1 + 4, the largest rating difference accepted
+}
+
+#[derive(BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct QueueAccount {
+    pub config: GameConfig,       // used for every game
+    pub games_created: u64,       // 8, also the index of the next game
+    pub entries: Vec<QueueEntry>, // oldest first
+}
+
+pub fn queue_address(program_id: &Pubkey, id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"queue", &id.to_le_bytes()], program_id).0
+}
+
+/// Address of game `index` set up by a queue, counting from 0
+pub fn game_address(program_id: &Pubkey, queue: &Pubkey, index: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"game", queue.as_ref(), &index.to_le_bytes()], program_id).0
+}
+
+/// A matchmaking queue, found at the PDA of its id
+pub struct Queue<'a> {
+    pub program_id: &'a Pubkey,
+    pub rpc_client: &'a RpcClient,
+    pub id: u64,
+    /// Only used when the queue gets created
+    pub config: GameConfig,
+}
+
+impl<'a> Queue<'a> {
+    pub fn new(program_id: &'a Pubkey, rpc_client: &'a RpcClient, id: u64) -> Self {
+        Self {
+            program_id,
+            rpc_client,
+            id,
+            config: GameConfig::default(),
+        }
+    }
+
+    pub fn queue_id(&self) -> Pubkey {
+        queue_address(self.program_id, self.id)
+    }
+
+    pub fn create_if_needed(&self, payer: &Keypair) -> QueueAccount {
+        if self.rpc_client.get_account(&self.queue_id()).is_ok() {
+            return self.get_queue_account();
+        }
+
+        let mut instruction_data = get_anchor_discriminator("global:create_queue").to_vec();
+        self.id.serialize(&mut instruction_data).unwrap();
+        self.config.serialize(&mut instruction_data).unwrap();
+
+        let create_queue_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(self.queue_id(), false),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        );
+
+        self.send_instruction(create_queue_instruction, payer, "creating queue")
+            .unwrap_or_else(|| std::process::exit(1));
+
+        self.get_queue_account()
+    }
+
+    /// Joins the queue, depositing its wager, to be paired with anyone whose
+    /// rating is within `band` of `player`'s.
+    pub fn enqueue(&self, player: &Keypair, band: Option<u32>) -> QueueAccount {
+        let mut instruction_data = get_anchor_discriminator("global:enqueue").to_vec();
+        band.serialize(&mut instruction_data).unwrap();
+
+        let enqueue_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(self.queue_id(), false),
+                AccountMeta::new(player.pubkey(), true),
+                AccountMeta::new_readonly(
+                    profile::profile_address(self.program_id, &player.pubkey()),
+                    false,
+                ),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        );
+
+        self.send_instruction(enqueue_instruction, player, "joining queue")
+            .unwrap_or_else(|| std::process::exit(1));
+
+        self.get_queue_account()
+    }
+
+    /// Leaves the queue, getting the deposit back
+    pub fn dequeue(&self, player: &Keypair) -> QueueAccount {
+        let instruction_data = get_anchor_discriminator("global:dequeue").to_vec();
+
+        let dequeue_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(self.queue_id(), false),
+                AccountMeta::new(player.pubkey(), true),
+            ],
+        );
+
+        self.send_instruction(dequeue_instruction, player, "leaving queue")
+            .unwrap_or_else(|| std::process::exit(1));
+
+        self.get_queue_account()
+    }
+
+    /// Sets up a game between the first two compatible players in the queue,
+    /// `payer` paying for it, and returns its address. Returns `None` when
+    /// no two players in the queue accept each other's rating.
+    pub fn pair(&self, payer: &Keypair) -> Option<Pubkey> {
+        let queue_account = self.get_queue_account();
+        let game = game_address(
+            self.program_id,
+            &self.queue_id(),
+            queue_account.games_created,
+        );
+        let instruction_data = get_anchor_discriminator("global:pair_players").to_vec();
+
+        let pair_players_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(self.queue_id(), false),
+                AccountMeta::new(game, false),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        );
+
+        self.send_instruction(pair_players_instruction, payer, "pairing players")
+            .map(|_| game)
+    }
+
+    pub fn get_queue_account(&self) -> QueueAccount {
+        let account = self.rpc_client.get_account(&self.queue_id()).unwrap();
+        let account_data = account.data();
+        assert!(account_data.len() > 8);
+        assert_eq!(&account_data[0..8], *ACCOUNT_QUEUE_DISCRIMINATOR);
+        QueueAccount::deserialize(&mut &account_data[8..]).unwrap()
+    }
+
+    /// Sends `instruction` in a transaction paid for by `signer`, returning
+    /// `None` if it failed.
+    fn send_instruction(
+        &self,
+        instruction: Instruction,
+        signer: &Keypair,
+        action: &str,
+    ) -> Option<()> {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&signer.pubkey()),
+            &[signer],
+            self.rpc_client.get_latest_blockhash().unwrap(),
+        );
+        send_transaction_and_print_logs(self.rpc_client, &transaction)
+            .map_err(|e| eprintln!("Error {}: {:?}", action, e))
+            .ok()
+    }
+}
+
+/// Puts `player` in the default queue, tries to pair them with someone
+/// already waiting and lists the games they have been paired into so far.
+pub fn find_game(program_id: &Pubkey, rpc_client: &RpcClient, player: &Keypair, band: Option<u32>) {
+    let queue = Queue::new(program_id, rpc_client, DEFAULT_QUEUE_ID);
+    queue.create_if_needed(player);
+    let queue_account = queue.get_queue_account();
+    if queue_account
+        .entries
+        .iter()
+        .all(|entry| entry.player != player.pubkey())
+    {
+        queue.enqueue(player, band);
+    }
+    if queue.get_queue_account().entries.len() > 1 && queue.pair(player).is_none() {
+        println!("Nobody in the queue is a match yet");
+    }
+
+    let games: Vec<_> = game::find_games(rpc_client, program_id, &player.pubkey())
+        .into_iter()
+        .filter(|(_, game_account)| game_account.state == GameState::Active)
+        .collect();
+    if games.is_empty() {
+        println!("Waiting in queue {} for an opponent", queue.queue_id());
+    }
+    for (address, game_account) in games {
+        println!(
+            "Game {} between {} and {}",
+            address, game_account.players[0], game_account.players[1]
+        );
+    }
+}
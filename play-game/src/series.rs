@@ -0,0 +1,206 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use once_cell::sync::Lazy;
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{
+    account::ReadableAccount,
+    instruction::{AccountMeta, Instruction},
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::Transaction,
+};
+
+use crate::{
+    game::{Game, GameConfig},
+    transaction::send_transaction_and_print_logs,
+    util::get_anchor_discriminator,
+};
+
+static ACCOUNT_SERIES_DISCRIMINATOR: Lazy<[u8; 8]> =
+    Lazy::new(|| get_anchor_discriminator("account:Series"));
+
+#[derive(BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SeriesAccount {
+    pub players: [Pubkey; 2],         // (32 * 2)
+    pub best_of: u8,                  // 1, always odd
+    pub config: GameConfig,           // used for every game
+    pub games_played: u32,            // 4, also the index of the next game
+    pub wins: [u8; 2],                // 1 * 2, ties don't count and are replayed
+    pub current_game: Option<Pubkey>, // 1 + 32, started but not yet scored
+    pub winner: Option<Pubkey>,       // 1 + 32
+    pub last_scored_at: i64,          // 8, when the series was created or last scored a game
+}
+
+/// Address of game `index` of a series, counting from 0
+pub fn game_address(program_id: &Pubkey, series: &Pubkey, index: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"game", series.as_ref(), &index.to_le_bytes()],
+        program_id,
+    )
+    .0
+}
+
+/// A best-of-N series between two players. Player one creates it and plays X
+/// in the first game, after which the players take turns.
+pub struct Series<'a> {
+    pub program_id: &'a Pubkey,
+    pub rpc_client: &'a RpcClient,
+    pub series_keypair: Keypair,
+    pub players: [&'a Keypair; 2],
+    pub best_of: u8,
+    pub config: GameConfig,
+}
+
+impl<'a> Series<'a> {
+    pub fn new(
+        program_id: &'a Pubkey,
+        rpc_client: &'a RpcClient,
+        player_one: &'a Keypair,
+        player_two: &'a Keypair,
+        best_of: u8,
+    ) -> Self {
+        Self {
+            program_id,
+            rpc_client,
+            series_keypair: Keypair::new(),
+            players: [player_one, player_two],
+            best_of,
+            config: GameConfig::default(),
+        }
+    }
+
+    pub fn series_id(&self) -> Pubkey {
+        self.series_keypair.pubkey()
+    }
+
+    pub fn create(&self) -> SeriesAccount {
+        let mut instruction_data = get_anchor_discriminator("global:create_series").to_vec();
+        self.players[1]
+            .pubkey()
+            .serialize(&mut instruction_data)
+            .unwrap();
+        self.best_of.serialize(&mut instruction_data).unwrap();
+        self.config.serialize(&mut instruction_data).unwrap();
+
+        let create_series_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(self.series_id(), true),
+                AccountMeta::new(self.players[0].pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        );
+
+        self.send_instruction(
+            create_series_instruction,
+            &[self.players[0], &self.series_keypair],
+            "creating series",
+        );
+
+        self.get_series_account()
+    }
+
+    /// Sets up the next game, with the player whose turn it is to play X
+    /// paying for it, and returns that game ready to be played.
+    pub fn start_next_game(&self) -> Game<'a> {
+        let series_account = self.get_series_account();
+        let index = series_account.games_played;
+        let first = (index % 2) as usize;
+        let game = Game {
+            config: self.config,
+            address: Some(game_address(self.program_id, &self.series_id(), index)),
+            ..Game::new(
+                self.program_id,
+                self.rpc_client,
+                self.players[first],
+                self.players[1 - first],
+            )
+        };
+
+        let instruction_data = get_anchor_discriminator("global:start_next_game").to_vec();
+        let start_next_game_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            [
+                vec![
+                    AccountMeta::new(self.series_id(), false),
+                    AccountMeta::new(game.game_id(), false),
+                    AccountMeta::new(game.player_one.pubkey(), true),
+                ],
+                game.token_accounts_omitted(6),
+                vec![AccountMeta::new_readonly(system_program::id(), false)],
+            ]
+            .concat(),
+        );
+
+        self.send_instruction(
+            start_next_game_instruction,
+            &[game.player_one],
+            "starting series game",
+        );
+
+        assert_eq!(game.get_game_account().players, game.players());
+        game
+    }
+
+    /// Scores the series' current game once it is over. Anyone may send
+    /// this, `payer` only pays the fee.
+    pub fn update(&self, game: &Game, payer: &Keypair) -> SeriesAccount {
+        let instruction_data = get_anchor_discriminator("global:update_series").to_vec();
+        let update_series_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(self.series_id(), false),
+                AccountMeta::new(game.game_id(), false),
+            ],
+        );
+
+        self.send_instruction(update_series_instruction, &[payer], "updating series");
+
+        self.get_series_account()
+    }
+
+    /// Claims the next game for `player`, due to play O in it, once the
+    /// player due to play X has let the move timeout pass without starting
+    /// it.
+    pub fn claim_forfeit(&self, player: &Keypair) -> SeriesAccount {
+        let instruction_data = get_anchor_discriminator("global:claim_series_forfeit").to_vec();
+        let claim_forfeit_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(self.series_id(), false),
+                AccountMeta::new_readonly(player.pubkey(), true),
+            ],
+        );
+
+        self.send_instruction(claim_forfeit_instruction, &[player], "claiming forfeit");
+
+        self.get_series_account()
+    }
+
+    pub fn get_series_account(&self) -> SeriesAccount {
+        let account = self.rpc_client.get_account(&self.series_id()).unwrap();
+        let account_data = account.data();
+        assert!(account_data.len() > 8);
+        assert_eq!(&account_data[0..8], *ACCOUNT_SERIES_DISCRIMINATOR);
+        SeriesAccount::deserialize(&mut &account_data[8..]).unwrap()
+    }
+
+    /// Sends `instruction` in a transaction paid for by the first of
+    /// `signers`.
+    fn send_instruction(&self, instruction: Instruction, signers: &[&Keypair], action: &str) {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&signers[0].pubkey()),
+            signers,
+            self.rpc_client.get_latest_blockhash().unwrap(),
+        );
+        if let Err(e) = send_transaction_and_print_logs(self.rpc_client, &transaction) {
+            eprintln!("Error {}: {:?}", action, e);
+            std::process::exit(1);
+        }
+    }
+}
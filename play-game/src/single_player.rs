@@ -0,0 +1,44 @@
+//! An interactive game against the program itself
+
+use std::io::{self, Write};
+
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+
+use crate::game::{Game, GameState};
+
+pub fn play_against_program(program_id: &Pubkey, rpc_client: &RpcClient, player: &Keypair) {
+    let mut game = Game::against_program(program_id, rpc_client, player);
+    game.print_balances = false;
+    game.setup_game();
+    let mut game_account = game.get_game_account();
+    game_account.print_board();
+
+    while game_account.state == GameState::Active {
+        print!("Your move (row column): ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).unwrap() == 0 {
+            game_account = game.resign(player);
+            break;
+        }
+        let tile: Vec<u8> = line
+            .split_whitespace()
+            .filter_map(|number| number.parse().ok())
+            .collect();
+        match tile[..] {
+            [row, col]
+                if row < 3
+                    && col < 3
+                    && game_account.board[row as usize * 3 + col as usize].is_none() =>
+            {
+                game_account = game.play((row, col));
+            }
+            _ => println!("Enter the row and column of an empty tile, each 0 to 2"),
+        }
+    }
+
+    println!("Game over: {:?}", game_account.state);
+    game.close_game();
+}
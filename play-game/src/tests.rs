@@ -1,11 +1,20 @@
+use std::{thread, time::Duration};
+
 use solana_client::rpc_client::RpcClient;
-use solana_program::pubkey::Pubkey;
+use solana_program::{native_token, pubkey::Pubkey};
 use solana_sdk::{signature::Keypair, signer::Signer};
 
-use crate::game::{
-    Game, GameAccount,
-    GameState::{Active, Tie, Won},
-    Sign::{O, X},
+use crate::{
+    game::{
+        FirstPlayer, Game, GameAccount, GameConfig, GameMode,
+        GameState::{Active, Resigned, Tie, TimedOut, Won},
+        Move, Rules,
+        Sign::{self, O, X},
+        Tile,
+    },
+    profile::get_player_profile,
+    queue::Queue,
+    series::Series,
 };
 
 fn new_game<'a>(
@@ -19,6 +28,24 @@ fn new_game<'a>(
     game
 }
 
+/// Flattens a classic 3x3 board into the row-major layout of the account
+fn board(rows: [[Option<Sign>; 3]; 3]) -> Vec<Option<Sign>> {
+    rows.concat()
+}
+
+/// Move history of a game where the players alternated on `tiles`
+fn moves(tiles: &[Tile]) -> Vec<Move> {
+    tiles
+        .iter()
+        .enumerate()
+        .map(|(i, &tile)| Move {
+            tile,
+            player: (i % 2) as u8,
+            layer: 0,
+        })
+        .collect()
+}
+
 pub(crate) fn play_player_one_wins_game(
     program_id: &Pubkey,
     rpc_client: &RpcClient,
@@ -27,77 +54,202 @@ pub(crate) fn play_player_one_wins_game(
 ) {
     let mut game = new_game(program_id, rpc_client, player_one, player_two);
 
+    let game_account = game.play((0, 0));
     assert_eq!(
-        game.play((0, 0)),
+        game_account,
         GameAccount {
+            version: 4,
             players: game.players(),
             state: Active,
-            board: [
+            board: board([
                 [Some(X), None, None],
                 [None, None, None],
                 [None, None, None]
-            ],
+            ]),
             turn: 2,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: game.player_one.pubkey(),
+            last_move_at: game_account.last_move_at,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            moves: moves(&[(0, 0)]),
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
         }
     );
 
+    let game_account = game.play((1, 0));
     assert_eq!(
-        game.play((1, 0)),
+        game_account,
         GameAccount {
+            version: 4,
             players: game.players(),
             state: Active,
-            board: [
+            board: board([
                 [Some(X), None, None],
                 [Some(O), None, None],
                 [None, None, None]
-            ],
+            ]),
             turn: 3,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: game.player_one.pubkey(),
+            last_move_at: game_account.last_move_at,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            moves: moves(&[(0, 0), (1, 0)]),
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
         }
     );
 
+    let game_account = game.play((0, 1));
     assert_eq!(
-        game.play((0, 1)),
+        game_account,
         GameAccount {
+            version: 4,
             players: game.players(),
             state: Active,
-            board: [
+            board: board([
                 [Some(X), Some(X), None],
                 [Some(O), None, None],
                 [None, None, None]
-            ],
+            ]),
             turn: 4,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: game.player_one.pubkey(),
+            last_move_at: game_account.last_move_at,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            moves: moves(&[(0, 0), (1, 0), (0, 1)]),
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
         }
     );
 
+    let game_account = game.play((1, 1));
     assert_eq!(
-        game.play((1, 1)),
+        game_account,
         GameAccount {
+            version: 4,
             players: game.players(),
             state: Active,
-            board: [
+            board: board([
                 [Some(X), Some(X), None],
                 [Some(O), Some(O), None],
                 [None, None, None]
-            ],
+            ]),
             turn: 5,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: game.player_one.pubkey(),
+            last_move_at: game_account.last_move_at,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            moves: moves(&[(0, 0), (1, 0), (0, 1), (1, 1)]),
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
         }
     );
 
+    let [player_one_before, player_two_before] = [player_one, player_two].map(|player| {
+        get_player_profile(rpc_client, program_id, &player.pubkey()).unwrap_or_default()
+    });
+
+    let game_account = game.play((0, 2));
     assert_eq!(
-        game.play((0, 2)),
+        game_account,
         GameAccount {
+            version: 4,
             players: game.players(),
             state: Won {
                 winner: game.player_one.pubkey()
             },
-            board: [
+            board: board([
                 [Some(X), Some(X), Some(X)],
                 [Some(O), Some(O), None],
                 [None, None, None]
-            ],
+            ]),
             turn: 5, // turn doesn't increment after the game ends
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: game.player_one.pubkey(),
+            last_move_at: game_account.last_move_at,
+            rating_changes: game_account.rating_changes,
+            draw_offer: None,
+            moves: moves(&[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)]),
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
         },
     );
+
+    // The result is recorded on both players' profiles
+    let [player_one_after, player_two_after] = [player_one, player_two]
+        .map(|player| get_player_profile(rpc_client, program_id, &player.pubkey()).unwrap());
+    assert_eq!(player_one_after.wins, player_one_before.wins + 1);
+    assert_eq!(player_two_after.losses, player_two_before.losses + 1);
+    assert!(game_account.rating_changes[0] > 0);
+    assert!(game_account.rating_changes[1] < 0);
+    assert_eq!(
+        player_one_after.rating,
+        player_one_before
+            .rating
+            .saturating_add_signed(game_account.rating_changes[0])
+    );
+
+    game.close_game();
 }
 
 pub(crate) fn tie_game(
@@ -108,129 +260,924 @@ pub(crate) fn tie_game(
 ) {
     let mut game = new_game(program_id, rpc_client, player_one, player_two);
 
+    let game_account = game.play((0, 0));
     assert_eq!(
-        game.play((0, 0)),
+        game_account,
         GameAccount {
+            version: 4,
             players: game.players(),
             state: Active,
-            board: [
+            board: board([
                 [Some(X), None, None],
                 [None, None, None],
                 [None, None, None]
-            ],
+            ]),
             turn: 2,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: game.player_one.pubkey(),
+            last_move_at: game_account.last_move_at,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            moves: moves(&[(0, 0)]),
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
         }
     );
 
+    let game_account = game.play((1, 1));
     assert_eq!(
-        game.play((1, 1)),
+        game_account,
         GameAccount {
+            version: 4,
             players: game.players(),
             state: Active,
-            board: [
+            board: board([
                 [Some(X), None, None],
                 [None, Some(O), None],
                 [None, None, None]
-            ],
+            ]),
             turn: 3,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: game.player_one.pubkey(),
+            last_move_at: game_account.last_move_at,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            moves: moves(&[(0, 0), (1, 1)]),
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
         }
     );
 
+    let game_account = game.play((2, 0));
     assert_eq!(
-        game.play((2, 0)),
+        game_account,
         GameAccount {
+            version: 4,
             players: game.players(),
             state: Active,
-            board: [
+            board: board([
                 [Some(X), None, None],
                 [None, Some(O), None],
                 [Some(X), None, None]
-            ],
+            ]),
             turn: 4,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: game.player_one.pubkey(),
+            last_move_at: game_account.last_move_at,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            moves: moves(&[(0, 0), (1, 1), (2, 0)]),
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
         }
     );
 
+    let game_account = game.play((1, 0));
     assert_eq!(
-        game.play((1, 0)),
+        game_account,
         GameAccount {
+            version: 4,
             players: game.players(),
             state: Active,
-            board: [
+            board: board([
                 [Some(X), None, None],
                 [Some(O), Some(O), None],
                 [Some(X), None, None]
-            ],
+            ]),
             turn: 5,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: game.player_one.pubkey(),
+            last_move_at: game_account.last_move_at,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            moves: moves(&[(0, 0), (1, 1), (2, 0), (1, 0)]),
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
         }
     );
 
+    let game_account = game.play((1, 2));
     assert_eq!(
-        game.play((1, 2)),
+        game_account,
         GameAccount {
+            version: 4,
             players: game.players(),
             state: Active,
-            board: [
+            board: board([
                 [Some(X), None, None],
                 [Some(O), Some(O), Some(X)],
                 [Some(X), None, None]
-            ],
+            ]),
             turn: 6,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: game.player_one.pubkey(),
+            last_move_at: game_account.last_move_at,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            moves: moves(&[(0, 0), (1, 1), (2, 0), (1, 0), (1, 2)]),
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
         }
     );
 
+    let game_account = game.play((0, 1));
     assert_eq!(
-        game.play((0, 1)),
+        game_account,
         GameAccount {
+            version: 4,
             players: game.players(),
             state: Active,
-            board: [
+            board: board([
                 [Some(X), Some(O), None],
                 [Some(O), Some(O), Some(X)],
                 [Some(X), None, None]
-            ],
+            ]),
             turn: 7,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: game.player_one.pubkey(),
+            last_move_at: game_account.last_move_at,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            moves: moves(&[(0, 0), (1, 1), (2, 0), (1, 0), (1, 2), (0, 1)]),
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
         }
     );
 
+    let game_account = game.play((2, 1));
     assert_eq!(
-        game.play((2, 1)),
+        game_account,
         GameAccount {
+            version: 4,
             players: game.players(),
             state: Active,
-            board: [
+            board: board([
                 [Some(X), Some(O), None],
                 [Some(O), Some(O), Some(X)],
                 [Some(X), Some(X), None]
-            ],
+            ]),
             turn: 8,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: game.player_one.pubkey(),
+            last_move_at: game_account.last_move_at,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            moves: moves(&[(0, 0), (1, 1), (2, 0), (1, 0), (1, 2), (0, 1), (2, 1)]),
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
         }
     );
 
+    let game_account = game.play((2, 2));
     assert_eq!(
-        game.play((2, 2)),
+        game_account,
         GameAccount {
+            version: 4,
             players: game.players(),
             state: Active,
-            board: [
+            board: board([
                 [Some(X), Some(O), None],
                 [Some(O), Some(O), Some(X)],
                 [Some(X), Some(X), Some(O)]
-            ],
+            ]),
             turn: 9,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: game.player_one.pubkey(),
+            last_move_at: game_account.last_move_at,
+            rating_changes: [0, 0],
+            draw_offer: None,
+            moves: moves(&[
+                (0, 0),
+                (1, 1),
+                (2, 0),
+                (1, 0),
+                (1, 2),
+                (0, 1),
+                (2, 1),
+                (2, 2)
+            ]),
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
         }
     );
 
+    let game_account = game.play((0, 2));
     assert_eq!(
-        game.play((0, 2)),
+        game_account,
         GameAccount {
+            version: 4,
             players: game.players(),
             state: Tie,
-            board: [
+            board: board([
                 [Some(X), Some(O), Some(X)],
                 [Some(O), Some(O), Some(X)],
                 [Some(X), Some(X), Some(O)]
-            ],
+            ]),
             turn: 9,
+            config: GameConfig::default(),
+            wager_matched: false,
+            payer: game.player_one.pubkey(),
+            last_move_at: game_account.last_move_at,
+            rating_changes: game_account.rating_changes,
+            draw_offer: None,
+            moves: moves(&[
+                (0, 0),
+                (1, 1),
+                (2, 0),
+                (1, 0),
+                (1, 2),
+                (0, 1),
+                (2, 1),
+                (2, 2),
+                (0, 2)
+            ]),
+            mint: None,
+            meta_board: vec![],
+            next_sub_board: None,
+            first_player: 0,
+            commitments: [None; 2],
+            secrets: [None; 2],
+            remaining_time: [0; 2],
+            takeback_request: None,
+            rematch_offer: None,
+            round: 0,
+            owner: None,
+            scored: false,
+        }
+    );
+
+    game.close_game();
+}
+
+pub(crate) fn player_two_wins_wager_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let wager = native_token::sol_to_lamports(0.001);
+
+    let mut game = Game::new(program_id, rpc_client, player_one, player_two);
+    game.config.wager = wager;
+    game.setup_game();
+    let rent = rpc_client.get_balance(&game.game_id()).unwrap() - wager;
+    game.match_wager();
+    assert_eq!(
+        rpc_client.get_balance(&game.game_id()).unwrap(),
+        rent + 2 * wager
+    );
+
+    // player 2 takes the diagonal
+    game.play((0, 1));
+    game.play((0, 0));
+    game.play((1, 0));
+    game.play((1, 1));
+    game.play((2, 1));
+
+    let player_two_balance = rpc_client.get_balance(&player_two.pubkey()).unwrap();
+    let fee = native_token::sol_to_lamports(0.000005);
+    assert_eq!(
+        game.play((2, 2)).state,
+        Won {
+            winner: player_two.pubkey()
+        }
+    );
+
+    // The pot leaves the game account and only the rent stays behind
+    assert_eq!(rpc_client.get_balance(&game.game_id()).unwrap(), rent);
+    assert_eq!(
+        rpc_client.get_balance(&player_two.pubkey()).unwrap(),
+        player_two_balance + 2 * wager - fee
+    );
+
+    game.close_game();
+}
+
+pub(crate) fn player_one_resigns_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let mut game = new_game(program_id, rpc_client, player_one, player_two);
+
+    game.play((1, 1));
+    game.play((0, 0));
+
+    assert_eq!(
+        game.resign(player_one).state,
+        Resigned {
+            winner: player_two.pubkey()
+        }
+    );
+
+    game.close_game();
+}
+
+pub(crate) fn draw_by_agreement_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let mut game = new_game(program_id, rpc_client, player_one, player_two);
+
+    game.play((1, 1));
+
+    // An offer lapses as soon as the next move is played
+    assert_eq!(
+        game.offer_draw(player_one).draw_offer,
+        Some(player_one.pubkey())
+    );
+    assert_eq!(game.play((0, 0)).draw_offer, None);
+
+    game.offer_draw(player_two);
+    let game_account = game.accept_draw(player_one);
+    assert_eq!(game_account.state, Tie);
+    assert_eq!(game_account.draw_offer, None);
+
+    game.close_game();
+}
+
+pub(crate) fn takeback_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let mut game = new_game(program_id, rpc_client, player_one, player_two);
+
+    game.play((0, 0));
+    game.play((1, 1));
+    assert_eq!(
+        game.request_takeback(player_two).takeback_request,
+        Some(player_two.pubkey())
+    );
+    let game_account = game.accept_takeback(player_one);
+    assert_eq!(game_account.turn, 2);
+    assert_eq!(game_account.board[4], None);
+    assert_eq!(game_account.takeback_request, None);
+
+    // Taking back the winning move reopens the game and clears the result
+    game.play((1, 0));
+    game.play((0, 1));
+    game.play((2, 0));
+    let game_account = game.play((0, 2));
+    assert_eq!(
+        game_account.state,
+        Won {
+            winner: player_one.pubkey()
+        }
+    );
+    game.request_takeback(player_one);
+    let game_account = game.accept_takeback(player_two);
+    assert_eq!(game_account.state, Active);
+    assert_eq!(game_account.rating_changes, [0, 0]);
+
+    game.resign(player_one);
+    game.close_game();
+}
+
+pub(crate) fn rematch_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let mut game = new_game(program_id, rpc_client, player_one, player_two);
+
+    game.play((0, 0));
+    game.play((1, 0));
+    game.play((0, 1));
+    game.play((1, 1));
+    game.play((0, 2));
+
+    // The same account hosts the next round, with player two now playing X
+    assert_eq!(
+        game.rematch(player_one).rematch_offer,
+        Some(player_one.pubkey())
+    );
+    let game_account = game.rematch(player_two);
+    assert_eq!(game_account.state, Active);
+    assert_eq!(game_account.round, 1);
+    assert_eq!(game_account.moves, vec![]);
+    assert_eq!(game_account.current_player_index(), 1);
+
+    game.play((1, 1));
+    let game_account = game.play((0, 0));
+    assert_eq!(game_account.board[4], Some(X));
+    assert_eq!(game_account.moves[0].player, 1);
+
+    game.resign(player_one);
+    game.close_game();
+}
+
+pub(crate) fn player_two_wins_on_timeout_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let mut game = Game::new(program_id, rpc_client, player_one, player_two);
+    game.config.move_timeout = 1;
+    game.setup_game();
+
+    game.play((1, 1));
+    game.play((0, 0));
+
+    // Player one walks away and player two claims the game
+    thread::sleep(Duration::from_secs(2));
+    assert_eq!(
+        game.claim_timeout(player_two).state,
+        TimedOut {
+            winner: player_two.pubkey()
+        }
+    );
+
+    game.close_game();
+}
+
+pub(crate) fn player_one_loses_on_time_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let mut game = Game::new(program_id, rpc_client, player_one, player_two);
+    game.config.clock = 3;
+    game.config.increment = 1;
+    game.setup_game();
+
+    game.play((1, 1));
+    let game_account = game.play((0, 0));
+    assert!(game_account.remaining_time.iter().all(|&time| time > 0));
+
+    // Player one runs out of time and player two flags them
+    thread::sleep(Duration::from_secs(5));
+    assert_eq!(
+        game.flag(player_two).state,
+        TimedOut {
+            winner: player_two.pubkey()
+        }
+    );
+
+    game.close_game();
+}
+
+pub(crate) fn open_challenge_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let mut game = Game::new(program_id, rpc_client, player_one, player_two);
+    game.open_challenge = true;
+    game.config.wager = native_token::sol_to_lamports(0.001);
+    game.setup_game();
+    game.join_game();
+
+    game.play((0, 0));
+    game.play((1, 0));
+    game.play((0, 1));
+    game.play((1, 1));
+    assert_eq!(
+        game.play((0, 2)).state,
+        Won {
+            winner: player_one.pubkey()
+        }
+    );
+
+    game.close_game();
+}
+
+pub(crate) fn larger_board_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let mut game = Game::new(program_id, rpc_client, player_one, player_two);
+    game.config.board_size = 5;
+    game.config.win_length = 4;
+    game.setup_game();
+
+    // Three in a row no longer wins
+    game.play((4, 0));
+    game.play((0, 0));
+    game.play((4, 1));
+    game.play((0, 1));
+    assert_eq!(game.play((4, 2)).state, Active);
+    game.play((0, 2));
+    assert_eq!(
+        game.play((4, 3)).state,
+        Won {
+            winner: player_one.pubkey()
+        }
+    );
+
+    game.close_game();
+}
+
+pub(crate) fn ultimate_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let mut game = Game::new(program_id, rpc_client, player_one, player_two);
+    game.config.mode = GameMode::Ultimate;
+    game.setup_game();
+
+    // Player one takes the middle rows of the top three sub-boards, while
+    // player two keeps sending them back there
+    let tiles = [
+        (0, 3),
+        (3, 1),
+        (1, 4),
+        (4, 2),
+        (2, 5),
+        (5, 0),
+        (0, 4),
+        (4, 1),
+        (1, 5),
+        (5, 2),
+        (2, 3),
+        (3, 0),
+        (0, 5),
+        (5, 1),
+        (1, 3),
+        (3, 2),
+    ];
+    for tile in tiles {
+        let game_account = game.play(tile);
+        assert_eq!(game_account.state, Active);
+        assert_eq!(game_account.next_sub_board, Some(tile.1));
+    }
+    let game_account = game.play((2, 4));
+    assert_eq!(
+        game_account.state,
+        Won {
+            winner: player_one.pubkey()
+        }
+    );
+    assert_eq!(
+        game_account.meta_board,
+        [
+            Some(X),
+            Some(X),
+            Some(X),
+            Some(O),
+            None,
+            Some(O),
+            None,
+            None,
+            None
+        ]
+    );
+
+    game.close_game();
+}
+
+pub(crate) fn cube_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let mut game = Game::new(program_id, rpc_client, player_one, player_two);
+    game.config.board_size = 4;
+    game.config.win_length = 4;
+    game.config.mode = GameMode::Cube;
+    game.setup_game();
+
+    // Player one wins along the diagonal from corner to corner of the cube
+    game.play_cube((0, 0, 0));
+    game.play_cube((0, 0, 1));
+    game.play_cube((1, 1, 1));
+    game.play_cube((0, 0, 2));
+    game.play_cube((2, 2, 2));
+    game.play_cube((0, 0, 3));
+    let game_account = game.play_cube((3, 3, 3));
+    assert_eq!(
+        game_account.state,
+        Won {
+            winner: player_one.pubkey()
+        }
+    );
+    assert_eq!(
+        game_account.moves.last(),
+        Some(&Move {
+            tile: (3, 3),
+            player: 0,
+            layer: 3,
+        })
+    );
+
+    game.close_game();
+}
+
+pub(crate) fn misere_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let mut game = Game::new(program_id, rpc_client, player_one, player_two);
+    game.config.rules = Rules::Misere;
+    game.setup_game();
+
+    // Player one completes the top row and loses
+    game.play((0, 0));
+    game.play((1, 0));
+    game.play((0, 1));
+    game.play((1, 1));
+    let game_account = game.play((0, 2));
+    assert_eq!(
+        game_account.state,
+        Won {
+            winner: player_two.pubkey()
+        }
+    );
+
+    game.close_game();
+}
+
+pub(crate) fn random_first_player_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let mut game = Game::new(program_id, rpc_client, player_one, player_two);
+    game.config.first_player = FirstPlayer::Random;
+    game.config.move_timeout = 60;
+    game.setup_game();
+
+    // The low bits of the secrets differ, so player two plays X
+    let secrets = [[1; 32], [2; 32]];
+    game.commit_secret(player_one, &secrets[0]);
+    game.commit_secret(player_two, &secrets[1]);
+    game.reveal_secret(player_one, &secrets[0]);
+    let game_account = game.reveal_secret(player_two, &secrets[1]);
+    assert_eq!(game_account.first_player, 1);
+
+    let game_account = game.play((1, 1));
+    assert_eq!(game_account.board[4], Some(X));
+    assert_eq!(game_account.moves[0].player, 1);
+
+    game.resign(player_one);
+    game.close_game();
+}
+
+pub(crate) fn program_opponent_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+) {
+    let mut game = Game::against_program(program_id, rpc_client, player_one);
+    game.setup_game();
+
+    // Player one takes the first empty tile every turn and the program,
+    // playing perfectly, never loses
+    let mut game_account = game.get_game_account();
+    while game_account.state == Active {
+        let index = game_account.board.iter().position(Option::is_none).unwrap();
+        game_account = game.play(((index / 3) as u8, (index % 3) as u8));
+        if game_account.state == Active {
+            // The program has already replied
+            assert_eq!(game_account.turn % 2, 1);
+        }
+    }
+    assert_ne!(
+        game_account.state,
+        Won {
+            winner: player_one.pubkey()
+        }
+    );
+
+    game.close_game();
+}
+
+pub(crate) fn pda_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let nonce = 0;
+    let mut game = Game::new(program_id, rpc_client, player_one, player_two);
+    game.pda_nonce = Some(nonce);
+    game.setup_game();
+
+    // The address can be recovered from nothing but the players and the nonce
+    assert_eq!(
+        game.game_id(),
+        Game::pda_address(
+            program_id,
+            &player_one.pubkey(),
+            Some(&player_two.pubkey()),
+            nonce
+        )
+    );
+
+    game.play((0, 0));
+    game.play((1, 0));
+    game.play((0, 1));
+    game.play((1, 1));
+    game.play((0, 2));
+
+    // Closing the game frees the address for the same nonce to be reused
+    game.close_game();
+}
+
+pub(crate) fn best_of_three_series(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let series = Series::new(program_id, rpc_client, player_one, player_two, 3);
+    series.create();
+
+    // X takes the top row every game, and X alternates between the players
+    let mut series_account = series.get_series_account();
+    while series_account.winner.is_none() {
+        let mut game = series.start_next_game();
+        game.play((0, 0));
+        game.play((1, 0));
+        game.play((0, 1));
+        game.play((1, 1));
+        game.play((0, 2));
+        series_account = series.update(&game, player_one);
+        game.close_game();
+    }
+    assert_eq!(series_account.games_played, 3);
+    assert_eq!(series_account.wins, [2, 1]);
+    assert_eq!(series_account.winner, Some(player_one.pubkey()));
+}
+
+pub(crate) fn series_forfeit(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    let mut series = Series::new(program_id, rpc_client, player_one, player_two, 1);
+    series.config.move_timeout = 1;
+    series.create();
+
+    // Player one never starts the game they are due to play X in
+    thread::sleep(Duration::from_secs(2));
+    let series_account = series.claim_forfeit(player_two);
+    assert_eq!(series_account.games_played, 1);
+    assert_eq!(series_account.wins, [0, 1]);
+    assert_eq!(series_account.winner, Some(player_two.pubkey()));
+}
+
+pub(crate) fn queue_game(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    player_one: &Keypair,
+    player_two: &Keypair,
+) {
+    // A queue of its own, so nobody else waiting gets paired in between
+    let id = u64::from_le_bytes(Keypair::new().pubkey().to_bytes()[..8].try_into().unwrap());
+    let wager = native_token::sol_to_lamports(0.001);
+    let mut queue = Queue::new(program_id, rpc_client, id);
+    queue.config.wager = wager;
+    queue.create_if_needed(player_one);
+    let rent = rpc_client.get_balance(&queue.queue_id()).unwrap();
+
+    // Leaving the queue refunds the deposit
+    queue.enqueue(player_one, Some(100));
+    assert_eq!(
+        rpc_client.get_balance(&queue.queue_id()).unwrap(),
+        rent + wager
+    );
+    assert!(queue.dequeue(player_one).entries.is_empty());
+    assert_eq!(rpc_client.get_balance(&queue.queue_id()).unwrap(), rent);
+
+    queue.enqueue(player_one, Some(100));
+    assert!(queue.pair(player_one).is_none());
+    let queue_account = queue.enqueue(player_two, None);
+    assert_eq!(queue_account.entries.len(), 2);
+
+    // Player one has waited longer and plays X
+    let address = queue.pair(player_one).unwrap();
+    assert!(queue.get_queue_account().entries.is_empty());
+    assert_eq!(rpc_client.get_balance(&queue.queue_id()).unwrap(), rent);
+    let (_, game_account) = crate::game::find_games(rpc_client, program_id, &player_two.pubkey())
+        .into_iter()
+        .find(|(game, _)| *game == address)
+        .unwrap();
+    assert_eq!(
+        game_account.players,
+        [player_one.pubkey(), player_two.pubkey()]
+    );
+    assert!(game_account.wager_matched);
+
+    let mut game = Game {
+        address: Some(address),
+        ..Game::new(program_id, rpc_client, player_one, player_two)
+    };
+    game.config.wager = wager;
+    game.play((0, 0));
+    game.play((1, 0));
+    game.play((0, 1));
+    game.play((1, 1));
+    assert_eq!(
+        game.play((0, 2)).state,
+        Won {
+            winner: player_one.pubkey()
         }
     );
+    game.close_game();
 }
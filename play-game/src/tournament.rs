@@ -0,0 +1,293 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use once_cell::sync::Lazy;
+use solana_client::rpc_client::RpcClient;
+use solana_program::{native_token, pubkey::Pubkey};
+use solana_sdk::{
+    account::ReadableAccount,
+    instruction::{AccountMeta, Instruction},
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::Transaction,
+};
+
+use crate::{
+    config,
+    game::{Game, GameConfig, GameState, Tile},
+    profile, transaction,
+    transaction::send_transaction_and_print_logs,
+    util::get_anchor_discriminator,
+};
+
+static ACCOUNT_TOURNAMENT_DISCRIMINATOR: Lazy<[u8; 8]> =
+    Lazy::new(|| get_anchor_discriminator("account:Tournament"));
+
+#[derive(BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entrant {
+    pub player: Pubkey, // 32
+    pub rating: u32,    // 4, when registering, decides the seeding
+}
+
+#[derive(BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub players: [Pubkey; 2],   // (32 * 2)
+    pub winner: Option<Pubkey>, // 1 + 32
+}
+
+#[derive(BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TournamentAccount {
+    pub authority: Pubkey,      // 32, who created the tournament
+    pub size: u8,               // 1, players in the bracket: 8, 16 or 32
+    pub config: GameConfig,     // used for every game
+    pub entrants: Vec<Entrant>, // in order of registration
+    pub round: u8,              // 1, 0 while registration is open
+    pub matches: Vec<Match>,    // the current round only
+    pub winner: Option<Pubkey>, // 1 + 32
+}
+
+/// Address of the game of match `index` in `round` of a tournament
+pub fn game_address(program_id: &Pubkey, tournament: &Pubkey, round: u8, index: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"game", tournament.as_ref(), &[round], &[index]],
+        program_id,
+    )
+    .0
+}
+
+/// A single-elimination tournament, `authority` creating it and paying for
+/// all of its games.
+pub struct Tournament<'a> {
+    pub program_id: &'a Pubkey,
+    pub rpc_client: &'a RpcClient,
+    pub tournament_keypair: Keypair,
+    pub authority: &'a Keypair,
+    pub size: u8,
+    pub config: GameConfig,
+}
+
+impl<'a> Tournament<'a> {
+    pub fn new(
+        program_id: &'a Pubkey,
+        rpc_client: &'a RpcClient,
+        authority: &'a Keypair,
+        size: u8,
+    ) -> Self {
+        Self {
+            program_id,
+            rpc_client,
+            tournament_keypair: Keypair::new(),
+            authority,
+            size,
+            config: GameConfig::default(),
+        }
+    }
+
+    pub fn tournament_id(&self) -> Pubkey {
+        self.tournament_keypair.pubkey()
+    }
+
+    pub fn create(&self) -> TournamentAccount {
+        let mut instruction_data = get_anchor_discriminator("global:create_tournament").to_vec();
+        self.size.serialize(&mut instruction_data).unwrap();
+        self.config.serialize(&mut instruction_data).unwrap();
+
+        let create_tournament_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(self.tournament_id(), true),
+                AccountMeta::new(self.authority.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        );
+
+        self.send_instruction(
+            create_tournament_instruction,
+            &[self.authority, &self.tournament_keypair],
+            "creating tournament",
+        );
+
+        self.get_tournament_account()
+    }
+
+    pub fn join(&self, player: &Keypair) -> TournamentAccount {
+        let instruction_data = get_anchor_discriminator("global:join_tournament").to_vec();
+
+        let join_tournament_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(self.tournament_id(), false),
+                AccountMeta::new_readonly(player.pubkey(), true),
+                AccountMeta::new_readonly(
+                    profile::profile_address(self.program_id, &player.pubkey()),
+                    false,
+                ),
+            ],
+        );
+
+        self.send_instruction(join_tournament_instruction, &[player], "joining tournament");
+
+        self.get_tournament_account()
+    }
+
+    /// Sets up the game of match `index` in the current round.
+    pub fn start_match(&self, index: u8) -> Pubkey {
+        let round = self.get_tournament_account().round;
+        let game = game_address(self.program_id, &self.tournament_id(), round, index);
+        let mut instruction_data = get_anchor_discriminator("global:start_match").to_vec();
+        index.serialize(&mut instruction_data).unwrap();
+
+        let start_match_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new_readonly(self.tournament_id(), false),
+                AccountMeta::new(game, false),
+                AccountMeta::new(self.authority.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        );
+
+        self.send_instruction(start_match_instruction, &[self.authority], "starting match");
+
+        game
+    }
+
+    /// Reads the outcome of match `index` into the bracket, replaying the
+    /// game if it was a tie.
+    pub fn advance(&self, index: u8) -> TournamentAccount {
+        let round = self.get_tournament_account().round;
+        let game = game_address(self.program_id, &self.tournament_id(), round, index);
+        let mut instruction_data = get_anchor_discriminator("global:advance").to_vec();
+        index.serialize(&mut instruction_data).unwrap();
+
+        let advance_instruction = Instruction::new_with_bytes(
+            *self.program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(self.tournament_id(), false),
+                AccountMeta::new(game, false),
+            ],
+        );
+
+        self.send_instruction(advance_instruction, &[self.authority], "advancing");
+
+        self.get_tournament_account()
+    }
+
+    pub fn get_tournament_account(&self) -> TournamentAccount {
+        let account = self.rpc_client.get_account(&self.tournament_id()).unwrap();
+        let account_data = account.data();
+        assert!(account_data.len() > 8);
+        assert_eq!(&account_data[0..8], *ACCOUNT_TOURNAMENT_DISCRIMINATOR);
+        TournamentAccount::deserialize(&mut &account_data[8..]).unwrap()
+    }
+
+    /// Sends `instruction` in a transaction paid for by the first of
+    /// `signers`.
+    fn send_instruction(&self, instruction: Instruction, signers: &[&Keypair], action: &str) {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&signers[0].pubkey()),
+            signers,
+            self.rpc_client.get_latest_blockhash().unwrap(),
+        );
+        if let Err(e) = send_transaction_and_print_logs(self.rpc_client, &transaction) {
+            eprintln!("Error {}: {:?}", action, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// X takes the top row
+const X_WINS: [Tile; 5] = [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)];
+
+/// Nobody completes a line
+const TIE: [Tile; 9] = [
+    (0, 0),
+    (1, 1),
+    (2, 2),
+    (0, 2),
+    (2, 0),
+    (1, 0),
+    (1, 2),
+    (2, 1),
+    (0, 1),
+];
+
+/// Runs a whole 8-player tournament between fresh players funded by
+/// `payer`, who also pays for the tournament and its games. X wins every
+/// game, except that the first match of each round is tied once and
+/// replayed.
+pub fn play_tournament(program_id: &Pubkey, rpc_client: &RpcClient, payer: &Keypair) {
+    config::initialize_config_if_needed(rpc_client, program_id, payer, config::DEFAULT_K_FACTOR);
+
+    let players: Vec<Keypair> = (0..8).map(|_| Keypair::new()).collect();
+    let lamports = native_token::sol_to_lamports(0.01);
+    for player in &players {
+        transaction::transfer(rpc_client, lamports, payer, &player.pubkey());
+    }
+    let keypair = |pubkey: Pubkey| players.iter().find(|p| p.pubkey() == pubkey).unwrap();
+
+    let tournament = Tournament::new(program_id, rpc_client, payer, 8);
+    tournament.create();
+    for player in &players {
+        tournament.join(player);
+    }
+
+    let mut tournament_account = tournament.get_tournament_account();
+    assert_eq!(tournament_account.round, 1);
+    while tournament_account.winner.is_none() {
+        let round = tournament_account.round;
+        println!("Round {} of the tournament", round);
+        let matches = tournament_account.matches.clone();
+        for (index, m) in matches.iter().enumerate() {
+            let index = index as u8;
+            let address = tournament.start_match(index);
+            let mut players = m.players;
+            if index == 0 {
+                play_match(program_id, rpc_client, address, players.map(keypair), &TIE);
+                tournament.advance(index);
+                // The replay swaps who plays X
+                players.reverse();
+            }
+            play_match(
+                program_id,
+                rpc_client,
+                address,
+                players.map(keypair),
+                &X_WINS,
+            );
+            tournament_account = tournament.advance(index);
+        }
+        assert!(tournament_account.round == round + 1 || tournament_account.winner.is_some());
+    }
+    println!(
+        "Player {} wins the tournament",
+        tournament_account.winner.unwrap()
+    );
+
+    for player in &players {
+        transaction::sweep(rpc_client, player, &payer.pubkey());
+    }
+}
+
+fn play_match(
+    program_id: &Pubkey,
+    rpc_client: &RpcClient,
+    address: Pubkey,
+    [player_one, player_two]: [&Keypair; 2],
+    tiles: &[Tile],
+) {
+    let mut game = Game {
+        address: Some(address),
+        print_balances: false,
+        ..Game::new(program_id, rpc_client, player_one, player_two)
+    };
+    let mut game_account = game.get_game_account();
+    assert_eq!(game_account.players, game.players());
+    for &tile in tiles {
+        game_account = game.play(tile);
+    }
+    assert_ne!(game_account.state, GameState::Active);
+}
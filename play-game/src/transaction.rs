@@ -11,6 +11,8 @@ use solana_sdk::{
 use solana_transaction_status::option_serializer::OptionSerializer;
 use solana_transaction_status_client_types::{UiTransactionEncoding, UiTransactionStatusMeta};
 
+use crate::events::decode_event;
+
 pub fn send_transaction_and_print_logs(
     rpc_client: &RpcClient,
     transaction: &Transaction,
@@ -33,7 +35,10 @@ pub fn send_transaction_and_print_logs(
         if !logs.is_empty() {
             println!("Logs:");
             for log_message in logs {
-                println!("  {}", log_message);
+                match decode_event(&log_message) {
+                    Some(event) => println!("  Event: {:?}", event),
+                    None => println!("  {}", log_message),
+                }
             }
         }
     }